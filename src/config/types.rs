@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -97,6 +99,8 @@ pub enum ModuleConfig {
     Clock(ClockConfig),
     #[serde(rename = "battery")]
     Battery(BatteryConfig),
+    #[serde(rename = "power_profile")]
+    PowerProfile(PowerProfileConfig),
     #[serde(rename = "audio")]
     Audio(AudioConfig),
     #[serde(rename = "network")]
@@ -105,6 +109,8 @@ pub enum ModuleConfig {
     Memory(MemoryConfig),
     #[serde(rename = "swap")]
     Swap(SwapConfig),
+    #[serde(rename = "pressure")]
+    Pressure(PressureConfig),
     #[serde(rename = "workspaces")]
     Workspaces(WorkspacesConfig),
     #[serde(rename = "tray")]
@@ -162,12 +168,68 @@ fn default_clock_tooltip_format() -> String {
 pub struct BatteryConfig {
     #[serde(default = "default_battery_format")]
     pub format: String,
+    /// A power_supply directory, or "auto" to combine all system batteries
     #[serde(default = "default_battery_path")]
     pub path: String,
+    /// Seconds between sysfs reads; charger and status changes also trigger
+    /// an immediate read through kernel uevents
     #[serde(default = "default_battery_interval")]
     pub interval: u64,
     #[serde(default = "default_battery_max_charge")]
     pub max_charge: u8,
+    #[serde(default = "default_battery_backend")]
+    pub backend: BatteryBackend,
+    /// Where UPower peripheral batteries (mouse, keyboard, headset) appear
+    #[serde(default = "default_battery_peripherals")]
+    pub peripherals: PeripheralDisplay,
+    /// Low-battery levels in percent, scaled by `max_charge` like the icon;
+    /// a level is reached once the charge drops below it.
+    /// Each fires once per discharge and resets when the charger is plugged in.
+    #[serde(default = "default_battery_warn_at")]
+    pub warn_at: u8,
+    #[serde(default = "default_battery_critical_at")]
+    pub critical_at: u8,
+    pub action_at: Option<u8>,
+    /// Command run through `sh -lc` when `action_at` is reached
+    pub action: Option<String>,
+    /// Send desktop notifications when a level is reached
+    #[serde(default = "default_battery_notify")]
+    pub notify: bool,
+}
+
+fn default_battery_warn_at() -> u8 {
+    20
+}
+
+fn default_battery_critical_at() -> u8 {
+    10
+}
+
+fn default_battery_notify() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryBackend {
+    Sysfs,
+    UPower,
+}
+
+fn default_battery_backend() -> BatteryBackend {
+    BatteryBackend::Sysfs
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PeripheralDisplay {
+    None,
+    Tooltip,
+    Indicators,
+}
+
+fn default_battery_peripherals() -> PeripheralDisplay {
+    PeripheralDisplay::None
 }
 
 fn default_battery_format() -> String {
@@ -186,6 +248,18 @@ fn default_battery_interval() -> u64 {
     60
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PowerProfileConfig {
+    #[serde(default = "default_power_profile_format")]
+    pub format: String,
+    /// Replaces cycling through the profiles on click
+    pub on_click: Option<String>,
+}
+
+fn default_power_profile_format() -> String {
+    "{icon}".to_string()
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AudioConfig {
     #[serde(default = "default_audio_format")]
@@ -208,6 +282,12 @@ pub struct NetworkConfig {
     pub format: String,
     #[serde(default = "default_network_interval")]
     pub interval: u64,
+    #[serde(default = "default_network_backend")]
+    pub backend: NetworkBackend,
+    pub tooltip_format: Option<String>,
+    /// Wi-Fi icons from weakest to strongest signal
+    #[serde(default = "default_network_wifi_icons")]
+    pub wifi_icons: Vec<String>,
     pub on_click: Option<String>,
 }
 
@@ -219,6 +299,27 @@ fn default_network_interval() -> u64 {
     60
 }
 
+fn default_network_wifi_icons() -> Vec<String> {
+    vec![
+        "\u{f6aa}".to_string(), // fa-wifi-weak
+        "\u{f6ab}".to_string(), // fa-wifi-fair
+        "\u{f1eb}".to_string(), // fa-wifi
+    ]
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkBackend {
+    Auto,
+    NetworkManager,
+    Netlink,
+    Sysfs,
+}
+
+fn default_network_backend() -> NetworkBackend {
+    NetworkBackend::Auto
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MemoryConfig {
     #[serde(default = "default_memory_format")]
@@ -229,6 +330,32 @@ pub struct MemoryConfig {
     pub bar_width: i32,
     #[serde(default = "default_bar_height")]
     pub bar_height: i32,
+    #[serde(default = "default_memory_display")]
+    pub display: MemoryDisplay,
+    /// Largest processes listed in the tooltip (0 disables the scan)
+    #[serde(default = "default_memory_top_processes")]
+    pub top_processes: usize,
+    /// Sum processes that share a cgroup (e.g. a systemd app scope)
+    #[serde(default)]
+    pub group_by_cgroup: bool,
+}
+
+fn default_memory_top_processes() -> usize {
+    5
+}
+
+/// What the memory, swap and pressure modules draw: the mini bar, the rendered
+/// `format` text, or both side by side
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryDisplay {
+    Bar,
+    Text,
+    Both,
+}
+
+fn default_memory_display() -> MemoryDisplay {
+    MemoryDisplay::Both
 }
 
 fn default_memory_format() -> String {
@@ -257,6 +384,8 @@ pub struct SwapConfig {
     pub bar_width: i32,
     #[serde(default = "default_bar_height")]
     pub bar_height: i32,
+    #[serde(default = "default_memory_display")]
+    pub display: MemoryDisplay,
 }
 
 fn default_swap_format() -> String {
@@ -267,6 +396,63 @@ fn default_swap_interval() -> u64 {
     15
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PressureConfig {
+    #[serde(default = "default_pressure_format")]
+    pub format: String,
+    #[serde(default = "default_pressure_interval")]
+    pub interval: u64,
+    /// Any of "cpu", "memory", "io"
+    #[serde(default = "default_pressure_resources")]
+    pub resources: Vec<String>,
+    #[serde(default = "default_pressure_window")]
+    pub window: PressureWindow,
+    /// Stall percentage that adds the `warning` class
+    #[serde(default = "default_pressure_warning")]
+    pub warning: f64,
+    /// Stall percentage that adds the `critical` class and fills the bar
+    #[serde(default = "default_pressure_critical")]
+    pub critical: f64,
+    #[serde(default = "default_bar_width")]
+    pub bar_width: i32,
+    #[serde(default = "default_bar_height")]
+    pub bar_height: i32,
+    #[serde(default = "default_memory_display")]
+    pub display: MemoryDisplay,
+}
+
+/// Which PSI running average drives the bar and threshold classes
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureWindow {
+    Avg10,
+    Avg60,
+}
+
+fn default_pressure_format() -> String {
+    "{icon}".to_string()
+}
+
+fn default_pressure_interval() -> u64 {
+    5
+}
+
+fn default_pressure_resources() -> Vec<String> {
+    vec!["cpu".to_string(), "memory".to_string(), "io".to_string()]
+}
+
+fn default_pressure_window() -> PressureWindow {
+    PressureWindow::Avg10
+}
+
+fn default_pressure_warning() -> f64 {
+    10.0
+}
+
+fn default_pressure_critical() -> f64 {
+    40.0
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct WorkspacesConfig {
     #[serde(default = "default_workspaces_format")]
@@ -275,9 +461,92 @@ pub struct WorkspacesConfig {
     pub show_hidden: bool,
     #[serde(default = "default_workspaces_scroll")]
     pub scroll: bool,
+    /// Older spelling of `hooks.on_active_change`, used when that is unset
     pub sync_command: Option<String>,
+    /// `sync_command` only runs while this is set
     #[serde(default = "default_workspaces_sync_only_active")]
     pub sync_only_active: bool,
+    #[serde(default = "default_workspaces_output_filter")]
+    pub output_filter: OutputFilter,
+    /// Text shown between workspace groups, e.g. one group per monitor
+    pub group_separator: Option<String>,
+    /// Workspace name or index to the text `{icon}` shows; "default" covers
+    /// the rest
+    #[serde(default)]
+    pub name_icons: HashMap<String, String>,
+    /// Pixel size of the application icons `{windows}` shows
+    #[serde(default = "default_workspaces_window_icon_size")]
+    pub window_icon_size: i32,
+    #[serde(default = "default_workspaces_backend")]
+    pub backend: WorkspacesBackend,
+    #[serde(default)]
+    pub hooks: WorkspaceHooks,
+}
+
+/// Commands run when workspaces change. Each gets the workspace through
+/// `{name}`, `{index}` and `{group}` and the `FERRITEBAR_WS_NAME`,
+/// `FERRITEBAR_WS_INDEX` and `FERRITEBAR_WS_GROUP` variables.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WorkspaceHooks {
+    /// A workspace became active
+    pub on_active_change: Option<String>,
+    /// A workspace became urgent
+    pub on_urgent: Option<String>,
+    pub on_created: Option<String>,
+    pub on_removed: Option<String>,
+    /// Milliseconds a hook waits for changes to settle; only the last one in
+    /// that time runs, so scrolling through workspaces runs it once
+    #[serde(default = "default_workspace_hooks_debounce_ms")]
+    pub debounce_ms: u64,
+}
+
+impl Default for WorkspaceHooks {
+    fn default() -> Self {
+        Self {
+            on_active_change: None,
+            on_urgent: None,
+            on_created: None,
+            on_removed: None,
+            debounce_ms: default_workspace_hooks_debounce_ms(),
+        }
+    }
+}
+
+fn default_workspace_hooks_debounce_ms() -> u64 {
+    150
+}
+
+/// Where workspaces come from; `Auto` tries compositor IPC (sway, Hyprland,
+/// niri), then ext-workspace-v1, then SartWC
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspacesBackend {
+    Auto,
+    Ext,
+    Sartwc,
+    Sway,
+    Hyprland,
+    Niri,
+    /// Fake in-memory workspaces for tests
+    #[cfg(test)]
+    Mock,
+}
+
+fn default_workspaces_backend() -> WorkspacesBackend {
+    WorkspacesBackend::Auto
+}
+
+/// Which workspace groups a bar shows
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFilter {
+    /// Only groups on the monitor the bar is on
+    Current,
+    All,
+}
+
+fn default_workspaces_output_filter() -> OutputFilter {
+    OutputFilter::Current
 }
 
 fn default_workspaces_format() -> String {
@@ -292,6 +561,10 @@ fn default_workspaces_sync_only_active() -> bool {
     true
 }
 
+fn default_workspaces_window_icon_size() -> i32 {
+    16
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TrayConfig {
     #[serde(default = "default_tray_icon_size")]
@@ -437,10 +710,12 @@ impl ModuleConfig {
             ModuleConfig::ApiSpend(_) => "API Spend".to_string(),
             ModuleConfig::Clock(_) => "Clock".to_string(),
             ModuleConfig::Battery(_) => "Battery".to_string(),
+            ModuleConfig::PowerProfile(_) => "Power Profile".to_string(),
             ModuleConfig::Audio(_) => "Audio".to_string(),
             ModuleConfig::Network(_) => "Network".to_string(),
             ModuleConfig::Memory(_) => "Memory".to_string(),
             ModuleConfig::Swap(_) => "Swap".to_string(),
+            ModuleConfig::Pressure(_) => "Pressure".to_string(),
             ModuleConfig::Workspaces(_) => "Workspaces".to_string(),
             ModuleConfig::Tray(_) => "Tray".to_string(),
             ModuleConfig::Taskbar(_) => "Taskbar".to_string(),
@@ -453,7 +728,10 @@ impl ModuleConfig {
     /// Which section (left/center/right) this module was originally in
     pub fn default_section(&self) -> &'static str {
         match self {
-            ModuleConfig::Workspaces(_) | ModuleConfig::Memory(_) | ModuleConfig::Swap(_) => "left",
+            ModuleConfig::Workspaces(_)
+            | ModuleConfig::Memory(_)
+            | ModuleConfig::Swap(_)
+            | ModuleConfig::Pressure(_) => "left",
             ModuleConfig::Taskbar(_) => "center",
             _ => "right",
         }
@@ -1,8 +1,32 @@
 use std::path::PathBuf;
-use std::sync::OnceLock;
-use tokio::sync::broadcast;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
 
 static IPC_TX: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+static HANDLERS: OnceLock<Mutex<Vec<Handler>>> = OnceLock::new();
+
+/// How long `msg` waits for a module to answer a handled command
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Handler {
+    prefix: String,
+    tx: mpsc::Sender<Request>,
+}
+
+/// A command routed to the modules that registered its first word with
+/// [`handle`]. Every one of them gets a copy; the first to reply answers
+/// `ferritebar msg`, and dropping a copy without replying passes.
+pub struct Request {
+    pub command: String,
+    reply: mpsc::Sender<Result<(), String>>,
+}
+
+impl Request {
+    pub fn reply(&self, result: Result<(), String>) {
+        let _ = self.reply.try_send(result);
+    }
+}
 
 pub fn socket_path() -> PathBuf {
     let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
@@ -13,16 +37,68 @@ fn sender() -> &'static broadcast::Sender<String> {
     IPC_TX.get_or_init(|| broadcast::channel(32).0)
 }
 
+fn handlers() -> &'static Mutex<Vec<Handler>> {
+    HANDLERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
 pub fn subscribe() -> broadcast::Receiver<String> {
     sender().subscribe()
 }
 
+/// Receive commands whose first word is `prefix`, with a way to report
+/// success or failure back to `ferritebar msg`. Those commands are no longer
+/// broadcast to [`subscribe`]rs.
+pub fn handle(prefix: &str) -> mpsc::Receiver<Request> {
+    let (tx, rx) = mpsc::channel(8);
+    handlers().lock().unwrap().push(Handler {
+        prefix: prefix.to_string(),
+        tx,
+    });
+    rx
+}
+
+/// Hand `cmd` to the registered handlers and wait for the first answer.
+/// Returns `None` when no handler is registered for it.
+async fn dispatch(cmd: &str) -> Option<Result<(), String>> {
+    let prefix = cmd.split_whitespace().next().unwrap_or_default();
+    let targets: Vec<mpsc::Sender<Request>> = {
+        let mut handlers = handlers().lock().unwrap();
+        handlers.retain(|h| !h.tx.is_closed());
+        handlers
+            .iter()
+            .filter(|h| h.prefix == prefix)
+            .map(|h| h.tx.clone())
+            .collect()
+    };
+    if targets.is_empty() {
+        return None;
+    }
+
+    let (reply_tx, mut reply_rx) = mpsc::channel(1);
+    for tx in targets {
+        let request = Request {
+            command: cmd.to_string(),
+            reply: reply_tx.clone(),
+        };
+        let _ = tx.send(request).await;
+    }
+    drop(reply_tx);
+
+    // Resolves with `None` once every copy was dropped unanswered
+    let answer = tokio::time::timeout(REPLY_TIMEOUT, reply_rx.recv()).await;
+    Some(match answer {
+        Ok(Some(result)) => result,
+        Ok(None) => Err(format!("nothing handled {cmd:?}")),
+        Err(_) => Err(format!("timed out waiting for {cmd:?}")),
+    })
+}
+
 pub fn start_listener() {
     let path = socket_path();
     let _ = std::fs::remove_file(&path);
     let tx = sender().clone();
     crate::spawn(async move {
-        use tokio::io::AsyncReadExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
         use tokio::net::UnixListener;
         let listener = match UnixListener::bind(&path) {
             Ok(l) => l,
@@ -41,8 +117,20 @@ pub fn start_listener() {
                         if stream.read_to_end(&mut buf).await.is_ok() {
                             if let Ok(cmd) = String::from_utf8(buf) {
                                 let cmd = cmd.trim().to_string();
-                                if !cmd.is_empty() {
-                                    let _ = tx.send(cmd);
+                                if cmd.is_empty() {
+                                    return;
+                                }
+                                match dispatch(&cmd).await {
+                                    Some(Ok(())) => {
+                                        let _ = stream.write_all(b"ok\n").await;
+                                    }
+                                    Some(Err(e)) => {
+                                        let reply = format!("error: {e}\n");
+                                        let _ = stream.write_all(reply.as_bytes()).await;
+                                    }
+                                    None => {
+                                        let _ = tx.send(cmd);
+                                    }
                                 }
                             }
                         }
@@ -56,12 +144,20 @@ pub fn start_listener() {
     });
 }
 
+/// Send a command to the running bar. Fails if the bar reports an error for
+/// a command a module handles.
 pub async fn send_msg(command: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use tokio::io::AsyncWriteExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::UnixStream;
     let path = socket_path();
     let mut stream = UnixStream::connect(&path).await?;
     stream.write_all(command.as_bytes()).await?;
     stream.shutdown().await?;
-    Ok(())
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).await?;
+    match reply.trim().strip_prefix("error: ") {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
 }
@@ -3,8 +3,10 @@ mod bar;
 mod config;
 mod ipc;
 mod modules;
+mod notify;
 mod power_menu;
 mod settings;
+mod sys;
 mod theme;
 mod toggle_menu;
 mod widgets;
@@ -54,14 +56,13 @@ fn main() {
     let wcag_report = args.iter().skip(1).any(|arg| arg == "--wcag");
 
     if args.get(1).map(|s| s.as_str()) == Some("msg") {
-        let cmd = match args.get(2) {
-            Some(c) => c.as_str(),
-            None => {
-                eprintln!("usage: ferritebar msg <command>");
-                std::process::exit(1);
-            }
-        };
-        if let Err(e) = runtime().block_on(ipc::send_msg(cmd)) {
+        // Multi-word commands (`msg workspace create web`) arrive as separate args
+        let cmd = args[2..].join(" ");
+        if cmd.is_empty() {
+            eprintln!("usage: ferritebar msg <command>");
+            std::process::exit(1);
+        }
+        if let Err(e) = runtime().block_on(ipc::send_msg(&cmd)) {
             eprintln!("ferritebar msg: {e}");
             std::process::exit(1);
         }
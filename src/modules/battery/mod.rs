@@ -0,0 +1,814 @@
+use std::fmt::Write;
+
+use gtk::prelude::*;
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+
+use crate::config::types::{BatteryBackend, BatteryConfig, PeripheralDisplay};
+use crate::notify::{self, Urgency};
+
+mod uevent;
+mod upower;
+
+#[derive(Debug, Clone, Copy)]
+enum BatteryStatus {
+    Charging,
+    Discharging,
+    NotCharging,
+    Full,
+    Unknown,
+}
+
+impl BatteryStatus {
+    fn parse(s: &str) -> Self {
+        match s.trim() {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Not charging" => Self::NotCharging,
+            "Full" => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Charging => "Charging",
+            Self::Discharging => "Discharging",
+            Self::NotCharging => "Not charging",
+            Self::Full => "Full",
+            Self::Unknown => "Unknown",
+        }
+    }
+
+    fn is_charging(self) -> bool {
+        matches!(self, Self::Charging)
+    }
+
+    /// Rank for combining several batteries: any charging battery makes the
+    /// pack charging, then any discharging one, and so on
+    fn priority(self) -> u8 {
+        match self {
+            Self::Charging => 4,
+            Self::Discharging => 3,
+            Self::NotCharging => 2,
+            Self::Full => 1,
+            Self::Unknown => 0,
+        }
+    }
+}
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+#[derive(Debug, Clone)]
+struct BatteryData {
+    /// False when UPower reports no system battery at all
+    present: bool,
+    percentage: u8,
+    status: BatteryStatus,
+    /// An AC adapter is online, even if charging is held off by thresholds.
+    /// `None` when the system has no Mains/USB supply to ask.
+    ac_online: Option<bool>,
+    /// Per-battery `(name, percentage)` when `path = "auto"` found several
+    batteries: Vec<(Box<str>, u8)>,
+    /// Wireless device batteries; only the UPower backend fills these in
+    peripherals: Vec<Peripheral>,
+    /// Remaining energy in Wh
+    energy: Option<f64>,
+    /// Last full charge in Wh
+    energy_full: Option<f64>,
+    energy_full_design: Option<f64>,
+    /// Charge or discharge rate in W, already smoothed by the reader task
+    power: Option<f64>,
+    cycle_count: Option<u32>,
+    /// Seconds until empty while discharging, or until full while charging
+    time: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+struct Peripheral {
+    name: &'static str,
+    icon: &'static str,
+    model: Box<str>,
+    percentage: u8,
+}
+
+impl BatteryData {
+    /// Full capacity as a percentage of design capacity
+    fn health(&self) -> Option<f64> {
+        match (self.energy_full, self.energy_full_design) {
+            (Some(full), Some(design)) if design > 0.0 => Some(full / design * 100.0),
+            _ => None,
+        }
+    }
+}
+
+/// Read a numeric sysfs attribute; a few drivers report signed currents
+fn read_attr(path: &str, name: &str) -> Option<f64> {
+    std::fs::read_to_string(format!("{path}/{name}"))
+        .ok()?
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .map(|v| v.unsigned_abs() as f64)
+}
+
+fn read_battery(path: &str) -> Option<BatteryData> {
+    let capacity = std::fs::read_to_string(format!("{path}/capacity"))
+        .ok()?
+        .trim()
+        .parse::<u8>()
+        .ok()?;
+    let raw_status = std::fs::read_to_string(format!("{path}/status")).ok()?;
+    let status = BatteryStatus::parse(&raw_status);
+
+    // Drivers report either energy (µWh, µW) or charge (µAh, µA); charge is
+    // converted with the present voltage (µV) so everything ends up in Wh/W.
+    let voltage = read_attr(path, "voltage_now").map(|uv| uv / 1e6);
+    let energy_attr = |energy: &str, charge: &str| -> Option<f64> {
+        read_attr(path, energy)
+            .or_else(|| Some(read_attr(path, charge)? * voltage?))
+            .map(|micro| micro / 1e6)
+    };
+    let power = read_attr(path, "power_now")
+        .or_else(|| Some(read_attr(path, "current_now")? * voltage?))
+        .map(|micro| micro / 1e6)
+        .filter(|&w| w > 0.0);
+
+    Some(BatteryData {
+        present: true,
+        percentage: capacity,
+        status,
+        ac_online: None,
+        batteries: Vec::new(),
+        peripherals: Vec::new(),
+        energy: energy_attr("energy_now", "charge_now"),
+        energy_full: energy_attr("energy_full", "charge_full"),
+        energy_full_design: energy_attr("energy_full_design", "charge_full_design"),
+        power,
+        cycle_count: read_attr(path, "cycle_count")
+            .map(|c| c as u32)
+            .filter(|&c| c > 0),
+        time: None,
+    })
+}
+
+fn read_supply_attr(dir: &std::path::Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join(name))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// System supplies under /sys/class/power_supply with the given `type`.
+/// Peripherals (mice, headsets) report `scope=Device` and are skipped.
+fn system_supplies(kinds: &[&str]) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(POWER_SUPPLY_DIR) else {
+        return Vec::new();
+    };
+    let mut supplies: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| {
+            read_supply_attr(dir, "type").is_some_and(|t| kinds.contains(&t.as_str()))
+                && read_supply_attr(dir, "scope").as_deref() != Some("Device")
+        })
+        .collect();
+    supplies.sort();
+    supplies
+}
+
+fn read_ac_online() -> Option<bool> {
+    let supplies = system_supplies(&["Mains", "USB"]);
+    if supplies.is_empty() {
+        return None;
+    }
+    Some(
+        supplies
+            .iter()
+            .any(|dir| read_supply_attr(dir, "online").as_deref() == Some("1")),
+    )
+}
+
+/// Combine every system battery into one pack, weighting by energy
+fn read_all_batteries() -> Option<BatteryData> {
+    let mut batteries: Vec<(Box<str>, BatteryData)> = system_supplies(&["Battery"])
+        .iter()
+        .filter_map(|dir| {
+            let name = dir.file_name()?.to_string_lossy().into();
+            Some((name, read_battery(dir.to_str()?)?))
+        })
+        .collect();
+    if batteries.len() <= 1 {
+        return batteries.pop().map(|(_, data)| data);
+    }
+
+    // None as soon as one battery lacks the value, so sums stay comparable
+    let sum = |f: fn(&BatteryData) -> Option<f64>| -> Option<f64> {
+        batteries.iter().map(|(_, b)| f(b)).sum()
+    };
+    let energy = sum(|b| b.energy);
+    let energy_full = sum(|b| b.energy_full);
+
+    let percentage = match (energy, energy_full) {
+        (Some(now), Some(full)) if full > 0.0 => (now / full * 100.0).round().min(100.0) as u8,
+        _ => {
+            let total: u32 = batteries.iter().map(|(_, b)| b.percentage as u32).sum();
+            (total / batteries.len() as u32) as u8
+        }
+    };
+    let status = batteries
+        .iter()
+        .map(|(_, b)| b.status)
+        .max_by_key(|s| s.priority())
+        .unwrap_or(BatteryStatus::Unknown);
+    // An idle battery reports no rate; only the active ones contribute
+    let power = batteries
+        .iter()
+        .filter_map(|(_, b)| b.power)
+        .reduce(|a, b| a + b);
+
+    Some(BatteryData {
+        present: true,
+        percentage,
+        status,
+        ac_online: None,
+        peripherals: Vec::new(),
+        energy,
+        energy_full,
+        energy_full_design: sum(|b| b.energy_full_design),
+        power,
+        cycle_count: batteries.iter().filter_map(|(_, b)| b.cycle_count).max(),
+        time: None,
+        batteries: batteries
+            .iter()
+            .map(|(name, b)| (name.clone(), b.percentage))
+            .collect(),
+    })
+}
+
+/// Read the configured battery (or all of them for `path = "auto"`) plus
+/// the AC adapter state
+fn read_power(path: &str) -> Option<BatteryData> {
+    let mut data = if path == "auto" {
+        read_all_batteries()?
+    } else {
+        read_battery(path)?
+    };
+    data.ac_online = read_ac_online();
+    Some(data)
+}
+
+/// Exponential moving average of the power draw, so the time estimate
+/// follows the trend instead of every load spike. Restarts whenever the
+/// charging state flips.
+struct PowerSmoother {
+    average: Option<f64>,
+    charging: bool,
+}
+
+impl PowerSmoother {
+    const ALPHA: f64 = 0.25;
+
+    fn new() -> Self {
+        Self {
+            average: None,
+            charging: false,
+        }
+    }
+
+    /// Replace `data.power` with the smoothed value and fill in `data.time`
+    fn apply(&mut self, data: &mut BatteryData) {
+        let charging = data.status.is_charging();
+        if charging != self.charging {
+            self.average = None;
+            self.charging = charging;
+        }
+
+        let Some(power) = data.power else {
+            self.average = None;
+            return;
+        };
+        let average = match self.average {
+            Some(avg) => avg + Self::ALPHA * (power - avg),
+            None => power,
+        };
+        self.average = Some(average);
+        data.power = Some(average);
+
+        let hours = match data.status {
+            BatteryStatus::Discharging => data.energy.map(|now| now / average),
+            BatteryStatus::Charging => data
+                .energy
+                .zip(data.energy_full)
+                .map(|(now, full)| (full - now).max(0.0) / average),
+            _ => None,
+        };
+        data.time = hours.map(|h| (h * 3600.0) as u64);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum AlertLevel {
+    None,
+    Warn,
+    Critical,
+    Action,
+}
+
+/// Remembers the deepest low-battery level already handled in the current
+/// discharge, so each level fires once until the charger is connected again
+struct LowBatteryAlerts {
+    warn_at: u8,
+    critical_at: u8,
+    action_at: Option<u8>,
+    fired: AlertLevel,
+}
+
+impl LowBatteryAlerts {
+    /// Returns the level that was newly reached, if any
+    fn update(&mut self, percentage: u8, plugged: bool) -> Option<AlertLevel> {
+        if plugged {
+            self.fired = AlertLevel::None;
+            return None;
+        }
+        let level = if self.action_at.is_some_and(|at| percentage < at) {
+            AlertLevel::Action
+        } else if percentage < self.critical_at {
+            AlertLevel::Critical
+        } else if percentage < self.warn_at {
+            AlertLevel::Warn
+        } else {
+            AlertLevel::None
+        };
+        // Jumping straight to a deeper level skips the shallower ones
+        if level > self.fired {
+            self.fired = level;
+            Some(level)
+        } else {
+            None
+        }
+    }
+}
+
+fn fire_alert(level: AlertLevel, percentage: u8, action: Option<&str>, notify: bool) {
+    let body = format!("{percentage}% remaining");
+    match level {
+        AlertLevel::None => {}
+        AlertLevel::Warn => {
+            if notify {
+                notify::send("Battery low".into(), body, "battery-low", Urgency::Normal);
+            }
+        }
+        AlertLevel::Critical => {
+            if notify {
+                notify::send(
+                    "Battery critical".into(),
+                    body,
+                    "battery-caution",
+                    Urgency::Critical,
+                );
+            }
+        }
+        AlertLevel::Action => {
+            let Some(cmd) = action else {
+                return;
+            };
+            warn!("Battery at {percentage}%, running `{cmd}`");
+            if notify {
+                notify::send(
+                    "Battery critical".into(),
+                    format!("{body}, running {cmd}"),
+                    "battery-empty",
+                    Urgency::Critical,
+                );
+            }
+            let cmd = cmd.to_string();
+            crate::spawn(async move {
+                let _ = tokio::process::Command::new("sh")
+                    .arg("-lc")
+                    .arg(&cmd)
+                    .spawn();
+            });
+        }
+    }
+}
+
+fn write_time(buf: &mut String, secs: u64) {
+    let _ = write!(buf, "{}:{:02}", secs / 3600, secs / 60 % 60);
+}
+
+fn battery_icon(percentage: u8, plugged: bool, max_charge: u8) -> &'static str {
+    if plugged {
+        return "\u{f1e6}"; // fa-plug
+    }
+    // Scale percentage relative to max_charge for icon selection
+    let effective = if max_charge > 0 && max_charge < 100 {
+        ((percentage as u16) * 100 / max_charge as u16).min(100) as u8
+    } else {
+        percentage
+    };
+    match effective {
+        90..=100 => "\u{F240}",
+        60..=89 => "\u{F241}",
+        30..=59 => "\u{F242}",
+        10..=29 => "\u{F243}",
+        _ => "\u{F244}",
+    }
+}
+
+fn update_indicators(container: &gtk::Box, peripherals: &[Peripheral]) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    for peripheral in peripherals {
+        let label = gtk::Label::new(Some(&format!(
+            "{}{}%",
+            peripheral.icon, peripheral.percentage
+        )));
+        label.add_css_class("peripheral");
+        if peripheral.percentage < 20 {
+            label.add_css_class("low");
+        }
+        super::set_tooltip_text(
+            label.clone(),
+            Some(&format!("{}: {}", peripheral.name, peripheral.model)),
+        );
+        container.append(&label);
+    }
+}
+
+fn format_tooltip(buf: &mut String, data: &BatteryData, max_charge: u8) {
+    if !data.present {
+        buf.push_str("No battery");
+    } else if max_charge < 100 {
+        let _ = write!(
+            buf,
+            "Battery: {}% / {}% max ({})",
+            data.percentage,
+            max_charge,
+            data.status.as_str()
+        );
+    } else {
+        let _ = write!(
+            buf,
+            "Battery: {}% ({})",
+            data.percentage,
+            data.status.as_str()
+        );
+    }
+
+    for (name, percentage) in &data.batteries {
+        let _ = write!(buf, "\n{name}: {percentage}%");
+    }
+    match data.ac_online {
+        Some(true) => buf.push_str("\nAC adapter: plugged in"),
+        Some(false) => buf.push_str("\nAC adapter: unplugged"),
+        None => {}
+    }
+
+    if let Some(secs) = data.time {
+        buf.push('\n');
+        write_time(buf, secs);
+        buf.push_str(if data.status.is_charging() {
+            " until full"
+        } else {
+            " remaining"
+        });
+    }
+    if let Some(watts) = data.power {
+        let _ = write!(buf, "\nPower: {watts:.1} W");
+    }
+    if let (Some(now), Some(full)) = (data.energy, data.energy_full) {
+        let _ = write!(buf, "\nEnergy: {now:.1} / {full:.1} Wh");
+    }
+    if let Some(health) = data.health() {
+        let _ = write!(buf, "\nHealth: {health:.0}%");
+        if let Some(design) = data.energy_full_design {
+            let _ = write!(buf, " of {design:.1} Wh design");
+        }
+    }
+    if let Some(cycles) = data.cycle_count {
+        let _ = write!(buf, "\nCycles: {cycles}");
+    }
+
+    for peripheral in &data.peripherals {
+        let _ = write!(buf, "\n{}: {}%", peripheral.name, peripheral.percentage);
+        if !peripheral.model.is_empty() {
+            let _ = write!(buf, " ({})", peripheral.model);
+        }
+    }
+}
+
+/// Run the uevent watcher on a blocking thread. Returns `false` if it could
+/// not start, so the caller falls back to plain interval polling.
+async fn run_uevent(tx: mpsc::Sender<BatteryData>, path: String, interval_secs: u64) -> bool {
+    let result =
+        tokio::task::spawn_blocking(move || uevent::watch(&tx, &path, interval_secs)).await;
+    match result {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            warn!("Battery uevent listener failed: {e}; polling sysfs");
+            false
+        }
+        Err(e) => {
+            error!("Battery uevent task panicked: {e}");
+            false
+        }
+    }
+}
+
+pub fn build(config: &BatteryConfig) -> gtk::Widget {
+    let (tx, rx) = mpsc::channel::<BatteryData>(8);
+
+    let path = config.path.clone();
+    let interval_secs = config.interval;
+    let backend = config.backend;
+
+    // Spawn controller
+    crate::spawn(async move {
+        if backend == BatteryBackend::UPower {
+            match upower::watch(&tx).await {
+                Ok(()) => return,
+                Err(e) => warn!("UPower battery backend failed: {e}; polling sysfs"),
+            }
+        }
+        if run_uevent(tx.clone(), path.clone(), interval_secs).await {
+            return;
+        }
+
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+        let mut smoother = PowerSmoother::new();
+        loop {
+            interval.tick().await;
+            if let Some(mut data) = read_power(&path) {
+                smoother.apply(&mut data);
+                if tx.send(data).await.is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    // Build widget — single label, no doubling
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    container.add_css_class("module");
+    container.add_css_class("battery");
+
+    let label = gtk::Label::new(None);
+    label.add_css_class("module-label");
+    container.append(&label);
+
+    let peripherals = config.peripherals;
+    let peripheral_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    peripheral_box.add_css_class("peripherals");
+    if peripherals == PeripheralDisplay::Indicators {
+        container.append(&peripheral_box);
+    }
+
+    let format = config.format.clone();
+    let max_charge = config.max_charge;
+    let warn_at = config.warn_at;
+    let critical_at = config.critical_at;
+    let action = config.action.clone();
+    let notify = config.notify;
+    let mut alerts = LowBatteryAlerts {
+        warn_at,
+        critical_at,
+        action_at: config.action_at.filter(|_| action.is_some()),
+        fired: AlertLevel::None,
+    };
+
+    // Bridge to GTK
+    let container_ref = container.clone();
+    let mut buf = String::with_capacity(64);
+    let mut tooltip_buf = String::with_capacity(64);
+    super::recv_on_main_thread(rx, move |mut data| {
+        if peripherals == PeripheralDisplay::None {
+            data.peripherals.clear();
+        } else if peripherals == PeripheralDisplay::Indicators {
+            update_indicators(&peripheral_box, &data.peripherals);
+        }
+        label.set_visible(data.present);
+
+        let charging = data.status.is_charging();
+        let plugged = charging || data.ac_online == Some(true);
+        let icon = battery_icon(data.percentage, plugged, max_charge);
+
+        buf.clear();
+        // Manual replacement into reusable buffer
+        for part in format.split('{') {
+            if let Some(rest) = part.strip_prefix("icon}") {
+                buf.push_str(icon);
+                buf.push_str(rest);
+            } else if let Some(rest) = part.strip_prefix("percentage}") {
+                let _ = write!(buf, "{}", data.percentage);
+                buf.push_str(rest);
+            } else if let Some(rest) = part.strip_prefix("status}") {
+                buf.push_str(data.status.as_str());
+                buf.push_str(rest);
+            } else if let Some(rest) = part.strip_prefix("time}") {
+                if let Some(secs) = data.time {
+                    write_time(&mut buf, secs);
+                }
+                buf.push_str(rest);
+            } else if let Some(rest) = part.strip_prefix("power}") {
+                if let Some(watts) = data.power {
+                    let _ = write!(buf, "{watts:.1}");
+                }
+                buf.push_str(rest);
+            } else if let Some(rest) = part.strip_prefix("health}") {
+                if let Some(health) = data.health() {
+                    let _ = write!(buf, "{health:.0}");
+                }
+                buf.push_str(rest);
+            } else {
+                buf.push_str(part);
+            }
+        }
+        label.set_label(&buf);
+
+        // Update CSS classes
+        container_ref.remove_css_class("charging");
+        container_ref.remove_css_class("plugged");
+        container_ref.remove_css_class("low");
+        container_ref.remove_css_class("critical");
+
+        // Scale thresholds relative to max_charge
+        let effective_pct = if max_charge > 0 && max_charge < 100 {
+            ((data.percentage as u16) * 100 / max_charge as u16).min(100) as u8
+        } else {
+            data.percentage
+        };
+
+        if plugged {
+            container_ref.add_css_class("plugged");
+        }
+        // A battery held at a charge threshold isn't draining, so it is
+        // never low while plugged in
+        if charging {
+            container_ref.add_css_class("charging");
+        } else if !plugged && effective_pct < critical_at {
+            container_ref.add_css_class("critical");
+        } else if !plugged && effective_pct < warn_at {
+            container_ref.add_css_class("low");
+        }
+
+        if let Some(level) = alerts.update(effective_pct, plugged || !data.present) {
+            fire_alert(level, data.percentage, action.as_deref(), notify);
+        }
+
+        tooltip_buf.clear();
+        format_tooltip(&mut tooltip_buf, &data, max_charge);
+        super::set_tooltip_text(container_ref.clone(), Some(&tooltip_buf));
+    });
+
+    debug!("Battery module created");
+    container.upcast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(status: BatteryStatus, energy: f64, power: Option<f64>) -> BatteryData {
+        BatteryData {
+            present: true,
+            percentage: 60,
+            status,
+            ac_online: None,
+            batteries: Vec::new(),
+            peripherals: Vec::new(),
+            energy: Some(energy),
+            energy_full: Some(50.0),
+            energy_full_design: Some(57.0),
+            power,
+            cycle_count: None,
+            time: None,
+        }
+    }
+
+    /// Smoothed `(power, time)` for one reading
+    fn smooth(smoother: &mut PowerSmoother, mut data: BatteryData) -> (Option<f64>, Option<u64>) {
+        smoother.apply(&mut data);
+        (data.power, data.time)
+    }
+
+    #[test]
+    fn smooths_power_and_estimates_time() {
+        let mut smoother = PowerSmoother::new();
+        let discharging = |power| reading(BatteryStatus::Discharging, 33.0, Some(power));
+
+        // The first sample is taken as is: 33 Wh at 10 W lasts 3.3 h
+        assert_eq!(
+            smooth(&mut smoother, discharging(10.0)),
+            (Some(10.0), Some(11880))
+        );
+        // Then each one moves the average a quarter of the way
+        assert_eq!(
+            smooth(&mut smoother, discharging(14.0)),
+            (Some(11.0), Some(10800))
+        );
+    }
+
+    #[test]
+    fn restarts_when_charging_flips() {
+        let mut smoother = PowerSmoother::new();
+        smooth(
+            &mut smoother,
+            reading(BatteryStatus::Discharging, 30.0, Some(10.0)),
+        );
+
+        // 20 Wh to go at 20 W, not averaged with the discharge rate
+        let charging = reading(BatteryStatus::Charging, 30.0, Some(20.0));
+        assert_eq!(smooth(&mut smoother, charging), (Some(20.0), Some(3600)));
+
+        let unplugged = reading(BatteryStatus::Discharging, 30.0, Some(6.0));
+        assert_eq!(smooth(&mut smoother, unplugged), (Some(6.0), Some(18000)));
+    }
+
+    #[test]
+    fn restarts_after_a_missing_rate() {
+        let mut smoother = PowerSmoother::new();
+        smooth(
+            &mut smoother,
+            reading(BatteryStatus::Discharging, 30.0, Some(10.0)),
+        );
+        assert_eq!(
+            smooth(
+                &mut smoother,
+                reading(BatteryStatus::Discharging, 30.0, None)
+            ),
+            (None, None)
+        );
+        assert_eq!(
+            smooth(
+                &mut smoother,
+                reading(BatteryStatus::Discharging, 30.0, Some(5.0))
+            ),
+            (Some(5.0), Some(21600))
+        );
+
+        // No estimate when neither charging nor discharging
+        assert_eq!(
+            smooth(
+                &mut smoother,
+                reading(BatteryStatus::NotCharging, 30.0, Some(5.0))
+            ),
+            (Some(5.0), None)
+        );
+    }
+
+    fn alerts(action_at: Option<u8>) -> LowBatteryAlerts {
+        LowBatteryAlerts {
+            warn_at: 20,
+            critical_at: 10,
+            action_at,
+            fired: AlertLevel::None,
+        }
+    }
+
+    #[test]
+    fn each_level_fires_once_per_discharge() {
+        let mut alerts = alerts(Some(5));
+        let fired: Vec<_> = [50, 20, 19, 18, 21, 19, 10, 9, 9, 5, 4, 3]
+            .into_iter()
+            .map(|percentage| alerts.update(percentage, false))
+            .collect();
+        assert_eq!(
+            fired,
+            [
+                None,
+                None,
+                Some(AlertLevel::Warn),
+                None,
+                None,
+                None,
+                None,
+                Some(AlertLevel::Critical),
+                None,
+                None,
+                Some(AlertLevel::Action),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn plugging_in_rearms() {
+        let mut alerts = alerts(Some(5));
+        assert_eq!(alerts.update(15, false), Some(AlertLevel::Warn));
+        assert_eq!(alerts.update(15, true), None);
+        assert_eq!(alerts.update(15, false), Some(AlertLevel::Warn));
+
+        // Unplugging already low goes straight to the deepest level
+        alerts.update(15, true);
+        assert_eq!(alerts.update(3, false), Some(AlertLevel::Action));
+        assert_eq!(alerts.update(8, false), None);
+    }
+
+    #[test]
+    fn no_action_level_without_a_threshold() {
+        let mut alerts = alerts(None);
+        assert_eq!(alerts.update(1, false), Some(AlertLevel::Critical));
+        assert_eq!(alerts.update(0, false), None);
+    }
+}
@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::AsRawFd;
+use std::os::raw::c_int;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use super::{read_power, BatteryData, PowerSmoother};
+use crate::sys::{self, poll_readable};
+
+const NETLINK_KOBJECT_UEVENT: c_int = 15;
+/// Multicast group the kernel broadcasts on (udev rebroadcasts on group 2)
+const UEVENT_GROUP_KERNEL: u32 = 1;
+
+/// Re-read the battery whenever the kernel reports a `power_supply` uevent
+/// (AC plugged or unplugged, battery status change), and otherwise once per
+/// `interval_secs` so the percentage keeps moving. Runs on a blocking thread.
+///
+/// Returns `Ok(())` once the receiver is dropped, or an error if the uevent
+/// socket cannot be opened (e.g. inside some containers).
+pub(super) fn watch(
+    tx: &mpsc::Sender<BatteryData>,
+    path: &str,
+    interval_secs: u64,
+) -> io::Result<()> {
+    let mut socket = UeventSocket::open()?;
+
+    debug!("Battery: listening for power_supply uevents");
+
+    let raw_fd = socket.as_raw_fd();
+    let refresh = Duration::from_secs(interval_secs.max(1));
+    let mut smoother = PowerSmoother::new();
+    let mut buf = vec![0u8; 8 * 1024];
+
+    loop {
+        if let Some(mut data) = read_power(path) {
+            smoother.apply(&mut data);
+            if tx.blocking_send(data).is_err() {
+                return Ok(());
+            }
+        }
+
+        let deadline = Instant::now() + refresh;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            let timeout_ms = remaining.as_millis().min(i32::MAX as u128) as i32;
+            if !poll_readable(raw_fd, timeout_ms) || !socket.recv_power_supply(&mut buf)? {
+                continue;
+            }
+            // Plugging in AC announces the adapter and every battery
+            // separately; take them all before re-reading sysfs.
+            while poll_readable(raw_fd, 200) {
+                socket.recv_power_supply(&mut buf)?;
+            }
+            break;
+        }
+    }
+}
+
+/// Kernel uevent listener
+struct UeventSocket {
+    file: File,
+}
+
+impl UeventSocket {
+    fn open() -> io::Result<Self> {
+        let fd = sys::netlink_socket(NETLINK_KOBJECT_UEVENT, UEVENT_GROUP_KERNEL)?;
+        Ok(Self {
+            file: File::from(fd),
+        })
+    }
+
+    fn as_raw_fd(&self) -> i32 {
+        self.file.as_raw_fd()
+    }
+
+    /// Read one uevent and report whether it came from the power_supply
+    /// subsystem. An overrun (ENOBUFS) counts as a match, since one of the
+    /// dropped events may have been ours.
+    fn recv_power_supply(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        match self.file.read(buf) {
+            Ok(n) => Ok(is_power_supply(&buf[..n])),
+            Err(e) if e.raw_os_error() == Some(sys::ENOBUFS) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Kernel uevents are `ACTION@DEVPATH` followed by NUL-separated `KEY=value`
+/// pairs.
+fn is_power_supply(msg: &[u8]) -> bool {
+    msg.split(|&b| b == 0)
+        .any(|field| field == b"SUBSYSTEM=power_supply")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_power_supply_uevents_only() {
+        let battery = b"change@/devices/LNXSYSTM:00/LNXSYBUS:00/PNP0C0A:00/power_supply/BAT0\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/LNXSYSTM:00/LNXSYBUS:00/PNP0C0A:00/power_supply/BAT0\0\
+                        SUBSYSTEM=power_supply\0\
+                        POWER_SUPPLY_NAME=BAT0\0\
+                        POWER_SUPPLY_STATUS=Discharging\0\
+                        SEQNUM=4711\0";
+        assert!(is_power_supply(battery));
+
+        // The battery's hwmon child sits under power_supply in the device
+        // path but belongs to another subsystem
+        let hwmon = b"add@/devices/LNXSYSTM:00/LNXSYBUS:00/PNP0C0A:00/power_supply/BAT0/hwmon3\0\
+                      ACTION=add\0\
+                      DEVPATH=/devices/LNXSYSTM:00/LNXSYBUS:00/PNP0C0A:00/power_supply/BAT0/hwmon3\0\
+                      SUBSYSTEM=hwmon\0\
+                      SEQNUM=4712\0";
+        assert!(!is_power_supply(hwmon));
+        assert!(!is_power_supply(b"SUBSYSTEM=power_supply_extra\0"));
+        assert!(!is_power_supply(b""));
+    }
+
+    #[test]
+    fn matches_libudev_rebroadcasts() {
+        // "libudev\0", then a binary header (magic 0xfeedcafe, sizes, filter
+        // hashes) ahead of the same NUL-separated properties
+        let mut msg = b"libudev\0".to_vec();
+        msg.extend_from_slice(&0xfeed_cafe_u32.to_be_bytes());
+        for field in [40u32, 40, 94, 0x1d2f_4b3c, 0, 0, 0] {
+            msg.extend_from_slice(&field.to_ne_bytes());
+        }
+        msg.extend_from_slice(
+            b"ACTION=change\0DEVPATH=/devices/platform/ac/power_supply/AC\0\
+              SUBSYSTEM=power_supply\0SEQNUM=4713\0",
+        );
+        assert!(is_power_supply(&msg));
+
+        // Cut off inside the SUBSYSTEM key
+        let truncated = &msg[..msg.len() - 30];
+        assert!(!is_power_supply(truncated));
+    }
+}
@@ -0,0 +1,286 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tracing::debug;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::{BatteryData, BatteryStatus, Peripheral};
+
+const UPOWER_SERVICE: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+
+// UpDeviceKind
+const KIND_LINE_POWER: u32 = 1;
+const KIND_BATTERY: u32 = 2;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn get_display_device(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower"
+)]
+trait Device {
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn power_supply(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn native_path(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn model(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn energy(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn energy_full(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn energy_full_design(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn energy_rate(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+
+    #[zbus(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+
+    /// Missing before UPower 0.99.14; -1 when unknown
+    #[zbus(property)]
+    fn charge_cycles(&self) -> zbus::Result<i32>;
+}
+
+/// Uncached proxy for a UPower device; each refresh reads what it needs once
+async fn device_proxy<'a>(
+    conn: &zbus::Connection,
+    path: OwnedObjectPath,
+) -> zbus::Result<DeviceProxy<'a>> {
+    DeviceProxy::builder(conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+/// Follow UPower's DisplayDevice (the combined system battery) and send a
+/// fresh snapshot whenever any UPower object changes.
+///
+/// Returns `Ok(())` once the receiver is dropped, or an error if UPower is
+/// not reachable so the caller can fall back to sysfs.
+pub(super) async fn watch(tx: &mpsc::Sender<BatteryData>) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let upower = UPowerProxy::builder(&conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // Fails with ServiceUnknown when UPower isn't installed
+    let display_path = upower.get_display_device().await?;
+
+    // PropertiesChanged on every device plus DeviceAdded/DeviceRemoved
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(UPOWER_SERVICE)?
+        .path_namespace(UPOWER_PATH)?
+        .build();
+    let mut signals = zbus::MessageStream::for_match_rule(rule, &conn, Some(64)).await?;
+
+    debug!("Battery: using UPower D-Bus backend");
+
+    loop {
+        let data = read_state(&conn, &upower, &display_path).await?;
+        if tx.send(data).await.is_err() {
+            return Ok(());
+        }
+
+        if signals.next().await.is_none() {
+            return Err(zbus::Error::Failure("UPower signal stream closed".into()));
+        }
+        // Plugging in AC updates a handful of properties at once
+        while let Ok(Some(_)) =
+            tokio::time::timeout(Duration::from_millis(150), signals.next()).await
+        {}
+    }
+}
+
+async fn read_state(
+    conn: &zbus::Connection,
+    upower: &UPowerProxy<'_>,
+    display_path: &OwnedObjectPath,
+) -> zbus::Result<BatteryData> {
+    let display = device_proxy(conn, display_path.clone()).await?;
+
+    let status = battery_status(display.state().await?);
+    let time = match status {
+        BatteryStatus::Charging => display.time_to_full().await?,
+        BatteryStatus::Discharging => display.time_to_empty().await?,
+        _ => 0,
+    };
+
+    let mut data = BatteryData {
+        // Desktops have a DisplayDevice too, but with nothing behind it
+        present: display.is_present().await?,
+        percentage: whole_percent(display.percentage().await?),
+        status,
+        ac_online: Some(!upower.on_battery().await?),
+        batteries: Vec::new(),
+        peripherals: Vec::new(),
+        energy: positive(display.energy().await?),
+        energy_full: positive(display.energy_full().await?),
+        energy_full_design: positive(display.energy_full_design().await?),
+        power: positive(display.energy_rate().await?),
+        cycle_count: None,
+        time: (time > 0).then_some(time as u64),
+    };
+
+    let mut batteries = Vec::new();
+    for path in upower.enumerate_devices().await? {
+        // Devices can disappear between enumerating and reading them
+        if let Err(e) = read_device(conn, path, &mut batteries, &mut data).await {
+            debug!("Battery: skipping UPower device: {e}");
+        }
+    }
+    if batteries.len() > 1 {
+        data.batteries = batteries;
+    }
+
+    Ok(data)
+}
+
+/// Sort one enumerated device into the per-battery list or the peripherals
+async fn read_device(
+    conn: &zbus::Connection,
+    path: OwnedObjectPath,
+    batteries: &mut Vec<(Box<str>, u8)>,
+    data: &mut BatteryData,
+) -> zbus::Result<()> {
+    let device = device_proxy(conn, path).await?;
+    let kind = device.kind().await?;
+    if kind == KIND_LINE_POWER || !device.is_present().await? {
+        return Ok(());
+    }
+    let percentage = whole_percent(device.percentage().await?);
+
+    if device.power_supply().await? {
+        if kind == KIND_BATTERY {
+            let native = device.native_path().await?;
+            batteries.push((native.into_boxed_str(), percentage));
+            if let Ok(cycles) = device.charge_cycles().await {
+                if cycles > 0 {
+                    data.cycle_count = data.cycle_count.max(Some(cycles as u32));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let (name, icon) = peripheral_kind(kind);
+    data.peripherals.push(Peripheral {
+        name,
+        icon,
+        model: device.model().await?.into_boxed_str(),
+        percentage,
+    });
+    Ok(())
+}
+
+/// Status for a UpDeviceState
+fn battery_status(state: u32) -> BatteryStatus {
+    match state {
+        1 => BatteryStatus::Charging,
+        // Discharging, Empty
+        2 | 3 => BatteryStatus::Discharging,
+        4 => BatteryStatus::Full,
+        // PendingCharge, PendingDischarge
+        5 | 6 => BatteryStatus::NotCharging,
+        _ => BatteryStatus::Unknown,
+    }
+}
+
+fn whole_percent(percentage: f64) -> u8 {
+    percentage.round().clamp(0.0, 100.0) as u8
+}
+
+/// Label and Font Awesome glyph for a UpDeviceKind
+fn peripheral_kind(kind: u32) -> (&'static str, &'static str) {
+    match kind {
+        5 => ("Mouse", "\u{f8cc}"),         // fa-computer-mouse
+        6 => ("Keyboard", "\u{f11c}"),      // fa-keyboard
+        8 => ("Phone", "\u{f3cd}"),         // fa-mobile-screen-button
+        10 => ("Tablet", "\u{f3fa}"),       // fa-tablet-screen-button
+        12 => ("Controller", "\u{f11b}"),   // fa-gamepad
+        13 => ("Pen", "\u{f304}"),          // fa-pen
+        14 => ("Touchpad", "\u{f25a}"),     // fa-hand-pointer
+        17 | 19 => ("Headset", "\u{f025}"), // fa-headphones
+        18 => ("Speaker", "\u{f028}"),      // fa-volume-high
+        _ => ("Device", "\u{f293}"),        // fa-bluetooth
+    }
+}
+
+fn positive(value: f64) -> Option<f64> {
+    (value > 0.0).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peripheral_kinds() {
+        assert_eq!(peripheral_kind(5).0, "Mouse");
+        assert_eq!(peripheral_kind(6).0, "Keyboard");
+        assert_eq!(peripheral_kind(12), ("Controller", "\u{f11b}"));
+        // Headset and headphones share a label
+        assert_eq!(peripheral_kind(17), peripheral_kind(19));
+        assert_eq!(peripheral_kind(18).0, "Speaker");
+        // Unknown, and kinds added after this table, show as a device
+        assert_eq!(peripheral_kind(0), ("Device", "\u{f293}"));
+        assert_eq!(peripheral_kind(99).0, "Device");
+    }
+
+    #[test]
+    fn display_device_values() {
+        assert!(matches!(battery_status(1), BatteryStatus::Charging));
+        assert!(matches!(battery_status(3), BatteryStatus::Discharging));
+        assert!(matches!(battery_status(4), BatteryStatus::Full));
+        assert!(matches!(battery_status(6), BatteryStatus::NotCharging));
+        assert!(matches!(battery_status(0), BatteryStatus::Unknown));
+
+        assert_eq!(whole_percent(54.6), 55);
+        assert_eq!(whole_percent(-1.0), 0);
+        assert_eq!(whole_percent(100.4), 100);
+
+        // UPower reports 0 for rates and energies it doesn't know
+        assert_eq!(positive(12.5), Some(12.5));
+        assert_eq!(positive(0.0), None);
+        assert_eq!(positive(-3.0), None);
+    }
+}
@@ -6,15 +6,24 @@ pub struct MemInfo {
     pub mem_available: u64,
     pub swap_total: u64,
     pub swap_free: u64,
+    /// zswap pool size (compressed), absent before Linux 5.19
+    pub zswap: Option<u64>,
+    /// Uncompressed size of the pages held in zswap
+    pub zswapped: Option<u64>,
 }
 
 /// Read and parse /proc/meminfo once for both memory and swap modules
 pub fn read_meminfo() -> Option<MemInfo> {
-    let contents = std::fs::read_to_string("/proc/meminfo").ok()?;
+    parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+fn parse_meminfo(contents: &str) -> Option<MemInfo> {
     let mut mem_total: u64 = 0;
     let mut mem_available: u64 = 0;
     let mut swap_total: u64 = 0;
     let mut swap_free: u64 = 0;
+    let mut zswap = None;
+    let mut zswapped = None;
 
     for line in contents.lines() {
         if let Some(rest) = line.strip_prefix("MemTotal:") {
@@ -25,6 +34,10 @@ pub fn read_meminfo() -> Option<MemInfo> {
             swap_total = parse_kb(rest)?;
         } else if let Some(rest) = line.strip_prefix("SwapFree:") {
             swap_free = parse_kb(rest)?;
+        } else if let Some(rest) = line.strip_prefix("Zswap:") {
+            zswap = parse_kb(rest);
+        } else if let Some(rest) = line.strip_prefix("Zswapped:") {
+            zswapped = parse_kb(rest);
         }
     }
 
@@ -33,10 +46,12 @@ pub fn read_meminfo() -> Option<MemInfo> {
         mem_available,
         swap_total,
         swap_free,
+        zswap,
+        zswapped,
     })
 }
 
-fn parse_kb(s: &str) -> Option<u64> {
+pub fn parse_kb(s: &str) -> Option<u64> {
     s.trim().trim_end_matches("kB").trim().parse::<u64>().ok()
 }
 
@@ -49,3 +64,70 @@ pub fn format_bytes_into(buf: &mut String, bytes: u64) {
         let _ = write!(buf, "{mib:.0} MiB");
     }
 }
+
+/// Byte counts behind the memory and swap `format` placeholders
+#[derive(Debug)]
+pub struct Usage {
+    pub used: u64,
+    pub total: u64,
+    pub available: u64,
+    pub swap_used: u64,
+}
+
+impl Usage {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used as f64 / self.total as f64
+        }
+    }
+}
+
+/// Expand `{icon}`, `{used}`, `{total}`, `{available}`, `{percentage}` and
+/// `{swap_used}` in a memory or swap format string into `buf`
+pub fn render_format(buf: &mut String, format: &str, icon: &str, usage: &Usage) {
+    for part in format.split('{') {
+        let Some((key, rest)) = part.split_once('}') else {
+            buf.push_str(part);
+            continue;
+        };
+        match key {
+            "icon" => buf.push_str(icon),
+            "used" => format_bytes_into(buf, usage.used),
+            "total" => format_bytes_into(buf, usage.total),
+            "available" => format_bytes_into(buf, usage.available),
+            "percentage" => {
+                let _ = write!(buf, "{:.0}", usage.fraction() * 100.0);
+            }
+            "swap_used" => format_bytes_into(buf, usage.swap_used),
+            _ => {
+                buf.push_str(part);
+                continue;
+            }
+        }
+        buf.push_str(rest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zswap_lines_are_optional() {
+        let before_5_19 = "MemTotal:       16314204 kB\n\
+                           MemAvailable:    9876543 kB\n\
+                           SwapTotal:       8388604 kB\n\
+                           SwapFree:        7340028 kB\n";
+        let info = parse_meminfo(before_5_19).unwrap();
+        assert_eq!((info.swap_total, info.swap_free), (8388604, 7340028));
+        assert_eq!((info.zswap, info.zswapped), (None, None));
+
+        let info = parse_meminfo(&format!(
+            "{before_5_19}Zswap:             51200 kB\nZswapped:         204800 kB\n"
+        ))
+        .unwrap();
+        assert_eq!((info.zswap, info.zswapped), (Some(51200), Some(204800)));
+    }
+}
@@ -1,23 +1,21 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::fmt::Write;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use gtk::prelude::*;
 use tokio::sync::mpsc;
 use tracing::debug;
 
-use crate::config::types::MemoryConfig;
+use crate::config::types::{MemoryConfig, MemoryDisplay};
 use crate::theme::ThemeColors;
 use crate::widgets::mini_bar::MiniBar;
 
 use super::meminfo;
 
-#[derive(Debug)]
-struct MemoryData {
-    used_bytes: u64,
-    total_bytes: u64,
-    fraction: f64,
-}
-
-fn read_memory() -> Option<MemoryData> {
+fn read_memory() -> Option<meminfo::Usage> {
     let info = meminfo::read_meminfo()?;
 
     if info.mem_total == 0 {
@@ -25,17 +23,186 @@ fn read_memory() -> Option<MemoryData> {
     }
 
     let used = info.mem_total.saturating_sub(info.mem_available);
-    let fraction = used as f64 / info.mem_total as f64;
 
-    Some(MemoryData {
-        used_bytes: used * 1024,
-        total_bytes: info.mem_total * 1024,
-        fraction,
+    Some(meminfo::Usage {
+        used: used * 1024,
+        total: info.mem_total * 1024,
+        available: info.mem_available * 1024,
+        swap_used: info.swap_total.saturating_sub(info.swap_free) * 1024,
     })
 }
 
+/// Minimum time between two process scans while the tooltip stays open
+const TOP_RESCAN: Duration = Duration::from_secs(2);
+
+struct Consumer {
+    name: String,
+    bytes: u64,
+    processes: usize,
+}
+
+/// One process as read from /proc
+struct Sample {
+    /// What it is summed under: its cgroup unit or its pid
+    key: String,
+    name: String,
+    rss_kb: u64,
+    pss_kb: Option<u64>,
+}
+
+/// The largest consumers and the measure they were ranked by
+struct Top {
+    measure: &'static str,
+    consumers: Vec<Consumer>,
+}
+
+/// Scan /proc for the largest memory users. With `group_by_cgroup`,
+/// processes in the same cgroup are summed under the cgroup's unit name.
+fn top_consumers(limit: usize, group_by_cgroup: bool) -> Top {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return rank(Vec::new(), limit);
+    };
+
+    let mut samples = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(pid) = file_name
+            .to_str()
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+        else {
+            continue;
+        };
+        let path = entry.path();
+        // Kernel threads have no VmRSS and are skipped here
+        let Some((comm, rss_kb)) = read_status(&path) else {
+            continue;
+        };
+
+        let (key, name) = match group_by_cgroup.then(|| read_cgroup_unit(&path)).flatten() {
+            Some(unit) => (unit.clone(), unit),
+            None => (pid.to_string(), comm),
+        };
+        samples.push(Sample {
+            key,
+            name,
+            rss_kb,
+            pss_kb: read_pss(&path),
+        });
+    }
+    rank(samples, limit)
+}
+
+/// Sum samples by key and keep the `limit` largest. PSS is only used when
+/// every process has it (running as root): RSS counts shared pages in full,
+/// so mixing the two would rank other users' processes above our own.
+fn rank(samples: Vec<Sample>, limit: usize) -> Top {
+    let by_pss = !samples.is_empty() && samples.iter().all(|s| s.pss_kb.is_some());
+
+    let mut consumers: HashMap<String, Consumer> = HashMap::new();
+    for sample in samples {
+        let kb = match sample.pss_kb {
+            Some(pss) if by_pss => pss,
+            _ => sample.rss_kb,
+        };
+        let consumer = consumers.entry(sample.key).or_insert(Consumer {
+            name: sample.name,
+            bytes: 0,
+            processes: 0,
+        });
+        consumer.bytes += kb * 1024;
+        consumer.processes += 1;
+    }
+
+    let mut consumers: Vec<Consumer> = consumers.into_values().collect();
+    consumers.sort_unstable_by_key(|c| std::cmp::Reverse(c.bytes));
+    consumers.truncate(limit);
+    Top {
+        measure: if by_pss { "PSS" } else { "RSS" },
+        consumers,
+    }
+}
+
+/// `(Name, VmRSS in kB)` from /proc/<pid>/status
+fn read_status(path: &Path) -> Option<(String, u64)> {
+    let status = std::fs::read_to_string(path.join("status")).ok()?;
+    let mut name = None;
+    let mut rss = None;
+    for line in status.lines() {
+        if let Some(rest) = line.strip_prefix("Name:") {
+            name = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("VmRSS:") {
+            rss = meminfo::parse_kb(rest);
+            break;
+        }
+    }
+    Some((name?, rss?))
+}
+
+/// PSS in kB; only readable for our own processes unless running as root
+fn read_pss(path: &Path) -> Option<u64> {
+    let rollup = std::fs::read_to_string(path.join("smaps_rollup")).ok()?;
+    rollup
+        .lines()
+        .find_map(|line| line.strip_prefix("Pss:"))
+        .and_then(meminfo::parse_kb)
+}
+
+fn read_cgroup_unit(path: &Path) -> Option<String> {
+    cgroup_unit(&std::fs::read_to_string(path.join("cgroup")).ok()?)
+}
+
+/// Leaf of a `/proc/<pid>/cgroup` v2 path with systemd decoration stripped,
+/// e.g. `app-firefox-1234.scope` -> `firefox`
+fn cgroup_unit(cgroup: &str) -> Option<String> {
+    let leaf = cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))?
+        .rsplit('/')
+        .next()?;
+
+    let unit = leaf
+        .trim_end_matches(".scope")
+        .trim_end_matches(".service")
+        .trim_end_matches(".slice");
+    let unit = unit.split('@').next().unwrap_or(unit);
+    let unit = unit.strip_prefix("app-").unwrap_or(unit);
+    let unit = unit.strip_prefix("flatpak-").unwrap_or(unit);
+    // Only a `-<digits>` instance suffix; digits in the name itself stay
+    let unit = match unit.rsplit_once('-') {
+        Some((name, id))
+            if !name.is_empty() && !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            name
+        }
+        _ => unit,
+    };
+    (!unit.is_empty()).then(|| unit.to_string())
+}
+
+fn format_top(buf: &mut String, top: &Top) {
+    if top.consumers.is_empty() {
+        return;
+    }
+    let _ = write!(buf, "\n\nTop processes by {}:", top.measure);
+    for consumer in &top.consumers {
+        buf.push('\n');
+        meminfo::format_bytes_into(buf, consumer.bytes);
+        let _ = write!(buf, "  {}", consumer.name);
+        if consumer.processes > 1 {
+            let _ = write!(buf, " ({})", consumer.processes);
+        }
+    }
+}
+
+fn show_tooltip(widget: &gtk::Box, summary: &str, top: &str) {
+    let mut text = String::with_capacity(summary.len() + top.len());
+    text.push_str(summary);
+    text.push_str(top);
+    super::set_tooltip_text(widget.clone(), Some(&text));
+}
+
 pub fn build(config: &MemoryConfig, colors: &ThemeColors) -> gtk::Widget {
-    let (tx, rx) = mpsc::channel::<MemoryData>(8);
+    let (tx, rx) = mpsc::channel::<meminfo::Usage>(8);
 
     let interval_secs = config.interval;
 
@@ -57,27 +224,81 @@ pub fn build(config: &MemoryConfig, colors: &ThemeColors) -> gtk::Widget {
     container.set_margin_start(0);
     container.set_margin_end(0);
 
-    // Icon
-    let icon_label = gtk::Label::new(Some("\u{f538}")); // fa-memory
-    icon_label.add_css_class("module-label");
-    container.append(&icon_label);
+    let label = gtk::Label::new(None);
+    label.add_css_class("module-label");
+    if config.display != MemoryDisplay::Bar {
+        container.append(&label);
+    }
 
     // Vertical mini bar
     let mini_bar = MiniBar::new(config.bar_width, config.bar_height, colors, true);
-    container.append(mini_bar.widget());
+    if config.display != MemoryDisplay::Text {
+        container.append(mini_bar.widget());
+    }
+
+    let summary = Rc::new(RefCell::new(String::with_capacity(64)));
+    let top = Rc::new(RefCell::new(String::new()));
 
+    // The process list is only built while someone is looking at it. This
+    // handler runs before the shared tooltip handler and lets it render.
+    if config.top_processes > 0 {
+        let (top_tx, top_rx) = mpsc::channel::<String>(1);
+        let limit = config.top_processes;
+        let group_by_cgroup = config.group_by_cgroup;
+        let scanning = Rc::new(Cell::new(false));
+        let last_scan: Rc<Cell<Option<Instant>>> = Rc::new(Cell::new(None));
+
+        let scanning_query = scanning.clone();
+        container.connect_query_tooltip(move |_, _, _, _, _| {
+            let stale = last_scan.get().is_none_or(|t| t.elapsed() >= TOP_RESCAN);
+            if !scanning_query.get() && stale {
+                scanning_query.set(true);
+                last_scan.set(Some(Instant::now()));
+                let top_tx = top_tx.clone();
+                crate::spawn(async move {
+                    let text = tokio::task::spawn_blocking(move || {
+                        let mut text = String::new();
+                        format_top(&mut text, &top_consumers(limit, group_by_cgroup));
+                        text
+                    })
+                    .await
+                    .unwrap_or_default();
+                    let _ = top_tx.send(text).await;
+                });
+            }
+            false
+        });
+
+        let container_top = container.clone();
+        let summary_top = summary.clone();
+        let top_ref = top.clone();
+        super::recv_on_main_thread(top_rx, move |text| {
+            scanning.set(false);
+            *top_ref.borrow_mut() = text;
+            show_tooltip(&container_top, &summary_top.borrow(), &top_ref.borrow());
+            container_top.trigger_tooltip_query();
+        });
+    }
+
+    let format = config.format.clone();
     let container_ref = container.clone();
-    let mut tooltip_buf = String::with_capacity(64);
-    super::recv_on_main_thread(rx, move |data| {
-        mini_bar.set_fraction(data.fraction);
-
-        tooltip_buf.clear();
-        tooltip_buf.push_str("Memory: ");
-        meminfo::format_bytes_into(&mut tooltip_buf, data.used_bytes);
-        tooltip_buf.push_str(" / ");
-        meminfo::format_bytes_into(&mut tooltip_buf, data.total_bytes);
-        let _ = write!(tooltip_buf, " ({:.0}%)", data.fraction * 100.0);
-        super::set_tooltip_text(container_ref.clone(), Some(&tooltip_buf));
+    let mut buf = String::with_capacity(32);
+    super::recv_on_main_thread(rx, move |usage| {
+        let fraction = usage.fraction();
+        mini_bar.set_fraction(fraction);
+
+        buf.clear();
+        meminfo::render_format(&mut buf, &format, "\u{f538}", &usage); // fa-memory
+        label.set_label(&buf);
+
+        let mut summary = summary.borrow_mut();
+        summary.clear();
+        summary.push_str("Memory: ");
+        meminfo::format_bytes_into(&mut summary, usage.used);
+        summary.push_str(" / ");
+        meminfo::format_bytes_into(&mut summary, usage.total);
+        let _ = write!(summary, " ({:.0}%)", fraction * 100.0);
+        show_tooltip(&container_ref, &summary, &top.borrow());
     });
 
     // IPC: toggle visibility when `ferritebar msg memory-toggle` is called
@@ -103,3 +324,105 @@ pub fn build(config: &MemoryConfig, colors: &ThemeColors) -> gtk::Widget {
     debug!("Memory module created");
     container.upcast()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(leaf: &str) -> Option<String> {
+        cgroup_unit(&format!(
+            "0::/user.slice/user-1000.slice/user@1000.service/app.slice/{leaf}\n"
+        ))
+    }
+
+    #[test]
+    fn strips_systemd_decoration() {
+        assert_eq!(unit("app-firefox-1234.scope").as_deref(), Some("firefox"));
+        assert_eq!(unit("foo@1.service").as_deref(), Some("foo"));
+        assert_eq!(
+            unit("app-flatpak-org.gimp.GIMP-5678.scope").as_deref(),
+            Some("org.gimp.GIMP")
+        );
+        assert_eq!(
+            unit("app-gnome-org.gnome.Nautilus-2741.scope").as_deref(),
+            Some("gnome-org.gnome.Nautilus")
+        );
+    }
+
+    #[test]
+    fn keeps_digits_that_belong_to_the_name() {
+        assert_eq!(
+            unit("app-qbittorrent5.scope").as_deref(),
+            Some("qbittorrent5")
+        );
+        assert_eq!(unit("app-sway-0.scope").as_deref(), Some("sway"));
+        assert_eq!(unit("app-k3s-agent.service").as_deref(), Some("k3s-agent"));
+        // A bare number isn't an instance suffix of anything
+        assert_eq!(unit("app-1234.scope").as_deref(), Some("1234"));
+    }
+
+    fn sample(key: &str, rss_kb: u64, pss_kb: Option<u64>) -> Sample {
+        Sample {
+            key: key.to_string(),
+            name: key.to_string(),
+            rss_kb,
+            pss_kb,
+        }
+    }
+
+    fn ranking(top: &Top) -> Vec<(&str, u64)> {
+        top.consumers
+            .iter()
+            .map(|c| (c.name.as_str(), c.bytes / 1024))
+            .collect()
+    }
+
+    #[test]
+    fn ranks_by_rss_unless_every_process_has_pss() {
+        // Our own process with PSS, someone else's with RSS only
+        let top = rank(
+            vec![sample("mine", 900, Some(300)), sample("theirs", 500, None)],
+            5,
+        );
+        assert_eq!(top.measure, "RSS");
+        assert_eq!(ranking(&top), [("mine", 900), ("theirs", 500)]);
+
+        let top = rank(
+            vec![
+                sample("mine", 900, Some(300)),
+                sample("theirs", 500, Some(400)),
+            ],
+            5,
+        );
+        assert_eq!(top.measure, "PSS");
+        assert_eq!(ranking(&top), [("theirs", 400), ("mine", 300)]);
+    }
+
+    #[test]
+    fn sums_by_key_and_keeps_the_largest() {
+        let top = rank(
+            vec![
+                sample("firefox", 300, None),
+                sample("foot", 50, None),
+                sample("firefox", 200, None),
+                sample("sway", 100, None),
+            ],
+            2,
+        );
+        assert_eq!(ranking(&top), [("firefox", 500), ("sway", 100)]);
+        assert_eq!(top.consumers[0].processes, 2);
+    }
+
+    #[test]
+    fn needs_a_v2_path() {
+        assert_eq!(
+            cgroup_unit("12:pids:/user.slice\n1:name=systemd:/user.slice\n"),
+            None
+        );
+        assert_eq!(cgroup_unit("0::/\n"), None);
+        assert_eq!(
+            cgroup_unit("0::/system.slice/systemd-journald.service\n").as_deref(),
+            Some("systemd-journald")
+        );
+    }
+}
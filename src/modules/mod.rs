@@ -6,6 +6,8 @@ pub mod clock;
 mod meminfo;
 pub mod memory;
 pub mod network;
+pub mod power_profile;
+pub mod pressure;
 pub mod script;
 pub mod swap;
 pub mod weather;
@@ -113,11 +115,25 @@ fn build_module(
         ModuleConfig::ApiSpend(cfg) => Some(api_spend::build(cfg)),
         ModuleConfig::Clock(cfg) => Some(clock::build(cfg)),
         ModuleConfig::Battery(cfg) => Some(battery::build(cfg)),
+        ModuleConfig::PowerProfile(cfg) => Some(power_profile::build(cfg)),
         ModuleConfig::Audio(cfg) => Some(audio::build(cfg)),
-        ModuleConfig::Network(cfg) => Some(network::build(cfg)),
+        ModuleConfig::Network(cfg) => Some(network::build(
+            cfg,
+            app,
+            bar_position,
+            bar_height,
+            bar_edge_margin,
+        )),
         ModuleConfig::Memory(cfg) => Some(memory::build(cfg, colors)),
         ModuleConfig::Swap(cfg) => Some(swap::build(cfg, colors)),
-        ModuleConfig::Workspaces(cfg) => Some(workspaces::build(cfg)),
+        ModuleConfig::Pressure(cfg) => Some(pressure::build(cfg, colors)),
+        ModuleConfig::Workspaces(cfg) => Some(workspaces::build(
+            cfg,
+            app,
+            bar_position,
+            bar_height,
+            bar_edge_margin,
+        )),
         ModuleConfig::Script(cfg) => Some(script::build(cfg)),
         ModuleConfig::Weather(cfg) => Some(weather::build(cfg)),
         ModuleConfig::Tray(cfg) => Some(tray::build(
@@ -127,7 +143,13 @@ fn build_module(
             bar_height,
             bar_edge_margin,
         )),
-        ModuleConfig::Taskbar(cfg) => Some(taskbar::build(cfg)),
+        ModuleConfig::Taskbar(cfg) => Some(taskbar::build(
+            cfg,
+            app,
+            bar_position,
+            bar_height,
+            bar_edge_margin,
+        )),
     }
 }
 
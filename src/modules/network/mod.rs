@@ -0,0 +1,408 @@
+use std::fmt::Write;
+
+use gtk::prelude::*;
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+
+use crate::config::types::{NetworkBackend, NetworkConfig, Position};
+
+mod netlink;
+mod nm;
+mod picker;
+
+#[derive(Debug, Default)]
+struct NetworkData {
+    connected: bool,
+    interface: Box<str>,
+    kind: NetKind,
+    ssid: Option<Box<str>>,
+    /// Wi-Fi signal strength in percent
+    signal: Option<u8>,
+    /// Wi-Fi channel frequency in MHz
+    frequency: Option<u32>,
+    /// First IPv4 address
+    ipv4: Option<Box<str>>,
+    /// Prefix length of `ipv4`
+    cidr: Option<u8>,
+    /// First non-link-local IPv6 address
+    ipv6: Option<Box<str>>,
+    /// Default gateway of the primary interface
+    gateway: Option<Box<str>>,
+    /// Active VPN connection or tunnel interface name
+    vpn: Option<Box<str>>,
+}
+
+#[derive(Debug, Default)]
+enum NetKind {
+    Wifi,
+    Ethernet,
+    #[default]
+    None,
+}
+
+async fn read_network() -> NetworkData {
+    // Scan /sys/class/net for any 'up' interface
+    if let Some(data) = try_sysfs().await {
+        return data;
+    }
+
+    NetworkData::default()
+}
+
+async fn try_sysfs() -> Option<NetworkData> {
+    let entries = std::fs::read_dir("/sys/class/net").ok()?;
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == "lo" {
+            continue;
+        }
+        if let Ok(state) = std::fs::read_to_string(entry.path().join("operstate")) {
+            if state.trim() == "up" {
+                // Detect wifi vs ethernet by checking for wireless subdir
+                let is_wifi = entry.path().join("wireless").exists() || name.starts_with('w');
+                let kind = if is_wifi {
+                    NetKind::Wifi
+                } else {
+                    NetKind::Ethernet
+                };
+
+                // Try to get SSID via iw for wifi interfaces
+                let ssid = if is_wifi {
+                    get_iw_ssid(&name).await
+                } else {
+                    None
+                };
+
+                return Some(NetworkData {
+                    connected: true,
+                    interface: name.into_boxed_str(),
+                    kind,
+                    ssid,
+                    vpn: sysfs_vpn(),
+                    ..NetworkData::default()
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// First tunnel interface that is up: tun/tap devices expose `tun_flags`,
+/// WireGuard tags its uevent with `DEVTYPE=wireguard`.
+fn sysfs_vpn() -> Option<Box<str>> {
+    for entry in std::fs::read_dir("/sys/class/net").ok()?.flatten() {
+        let path = entry.path();
+        let up = std::fs::read_to_string(path.join("flags"))
+            .ok()
+            .and_then(|f| u32::from_str_radix(f.trim().trim_start_matches("0x"), 16).ok())
+            .is_some_and(|flags| flags & 0x1 != 0);
+        if !up {
+            continue;
+        }
+        let is_vpn = path.join("tun_flags").exists()
+            || std::fs::read_to_string(path.join("uevent"))
+                .is_ok_and(|uevent| uevent.lines().any(|l| l == "DEVTYPE=wireguard"));
+        if is_vpn {
+            return Some(entry.file_name().to_string_lossy().into());
+        }
+    }
+    None
+}
+
+async fn get_iw_ssid(interface: &str) -> Option<Box<str>> {
+    let output = tokio::process::Command::new("iw")
+        .args(["dev", interface, "info"])
+        .output()
+        .await
+        .ok()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        let trimmed = line.trim();
+        if let Some(ssid) = trimmed.strip_prefix("ssid ") {
+            return Some(ssid.into());
+        }
+    }
+    None
+}
+
+fn network_icon<'a>(data: &NetworkData, wifi_icons: &'a [String]) -> &'a str {
+    match data.kind {
+        NetKind::Wifi if data.connected => wifi_icon(data.signal, wifi_icons),
+        NetKind::Ethernet if data.connected => "\u{f796}", // fa-ethernet
+        _ => "\u{f071}",                                   // fa-triangle-exclamation
+    }
+}
+
+/// Pick the ramp entry for a signal strength; unknown strength shows the
+/// strongest icon.
+fn wifi_icon(signal: Option<u8>, icons: &[String]) -> &str {
+    if icons.is_empty() {
+        return "\u{f1eb}"; // fa-wifi
+    }
+    let last = icons.len() - 1;
+    let idx = match signal {
+        Some(signal) => (signal.min(100) as usize * icons.len() / 101).min(last),
+        None => last,
+    };
+    &icons[idx]
+}
+
+/// Expand `{placeholder}`s in a network format string into `buf`
+fn render_format(buf: &mut String, format: &str, data: &NetworkData, icon: &str) {
+    for part in format.split('{') {
+        let Some((key, rest)) = part.split_once('}') else {
+            buf.push_str(part);
+            continue;
+        };
+        match key {
+            "icon" => buf.push_str(icon),
+            "ifname" => buf.push_str(&data.interface),
+            "ssid" | "essid" => buf.push_str(data.ssid.as_deref().unwrap_or("")),
+            "signal" => {
+                if let Some(signal) = data.signal {
+                    let _ = write!(buf, "{signal}");
+                }
+            }
+            "frequency" => {
+                if let Some(freq) = data.frequency {
+                    let _ = write!(buf, "{:.1}", freq as f64 / 1000.0);
+                }
+            }
+            "ipv4" => buf.push_str(data.ipv4.as_deref().unwrap_or("")),
+            "ipv6" => buf.push_str(data.ipv6.as_deref().unwrap_or("")),
+            "cidr" => {
+                if let Some(cidr) = data.cidr {
+                    let _ = write!(buf, "{cidr}");
+                }
+            }
+            "gateway" => buf.push_str(data.gateway.as_deref().unwrap_or("")),
+            "vpn" => buf.push_str(data.vpn.as_deref().unwrap_or("")),
+            _ => {
+                buf.push_str(part);
+                continue;
+            }
+        }
+        buf.push_str(rest);
+    }
+}
+
+fn default_tooltip(buf: &mut String, data: &NetworkData) {
+    match (&data.kind, &data.ssid) {
+        (NetKind::Wifi, Some(ssid)) => {
+            let _ = write!(buf, "WiFi: {ssid} ({})", data.interface);
+            if let Some(signal) = data.signal {
+                let _ = write!(buf, " {signal}%");
+            }
+        }
+        (NetKind::Wifi, None) => {
+            let _ = write!(buf, "WiFi: {} (connected)", data.interface);
+        }
+        (NetKind::Ethernet, _) => {
+            let _ = write!(buf, "Ethernet: {}", data.interface);
+        }
+        _ => {
+            buf.push_str("Disconnected");
+            return;
+        }
+    }
+    if let Some(ref ipv4) = data.ipv4 {
+        let _ = write!(buf, "\nIPv4: {ipv4}/{}", data.cidr.unwrap_or(32));
+    }
+    if let Some(ref ipv6) = data.ipv6 {
+        let _ = write!(buf, "\nIPv6: {ipv6}");
+    }
+    if let Some(ref gateway) = data.gateway {
+        let _ = write!(buf, "\nGateway: {gateway}");
+    }
+    if let Some(ref vpn) = data.vpn {
+        let _ = write!(buf, "\nVPN: {vpn}");
+    }
+}
+
+/// Run the netlink watcher on a blocking thread. Returns `false` if it could
+/// not start, so the caller falls back to sysfs polling.
+async fn run_netlink(tx: mpsc::Sender<NetworkData>, interval_secs: u64) -> bool {
+    let result = tokio::task::spawn_blocking(move || netlink::watch(&tx, interval_secs)).await;
+    match result {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            warn!("Netlink network backend failed: {e}; polling sysfs");
+            false
+        }
+        Err(e) => {
+            error!("Netlink watcher task panicked: {e}");
+            false
+        }
+    }
+}
+
+pub fn build(
+    config: &NetworkConfig,
+    app: &gtk::Application,
+    bar_position: Position,
+    bar_height: u32,
+    bar_edge_margin: i32,
+) -> gtk::Widget {
+    let (tx, rx) = mpsc::channel::<NetworkData>(8);
+
+    let interval_secs = config.interval;
+    let backend = config.backend;
+
+    crate::spawn(async move {
+        match backend {
+            NetworkBackend::Auto => {
+                match nm::watch(&tx, interval_secs).await {
+                    Ok(()) => return,
+                    Err(e) => debug!("NetworkManager unavailable ({e}); using netlink"),
+                }
+                if run_netlink(tx.clone(), interval_secs).await {
+                    return;
+                }
+            }
+            NetworkBackend::NetworkManager => {
+                // Explicitly requested: keep retrying instead of falling back
+                loop {
+                    match nm::watch(&tx, interval_secs).await {
+                        Ok(()) => return,
+                        Err(e) => warn!("NetworkManager backend failed: {e}"),
+                    }
+                    let _ = tx.send(NetworkData::default()).await;
+                    tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs.max(1)))
+                        .await;
+                    if tx.is_closed() {
+                        return;
+                    }
+                }
+            }
+            NetworkBackend::Netlink => {
+                if run_netlink(tx.clone(), interval_secs).await {
+                    return;
+                }
+            }
+            NetworkBackend::Sysfs => {}
+        }
+
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let data = read_network().await;
+            if tx.send(data).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    container.add_css_class("module");
+    container.add_css_class("network");
+
+    let label = gtk::Label::new(None);
+    label.add_css_class("module-label");
+    container.append(&label);
+
+    // Click handler: run on_click if set, otherwise toggle the Wi-Fi picker
+    let gesture = gtk::GestureClick::new();
+    if let Some(ref cmd) = config.on_click {
+        let on_click = cmd.clone();
+        gesture.connect_released(move |_, _, _, _| {
+            let cmd = on_click.clone();
+            crate::spawn(async move {
+                let _ = tokio::process::Command::new("sh")
+                    .arg("-lc")
+                    .arg(&cmd)
+                    .spawn();
+            });
+        });
+
+        // IPC: fire on_click when `ferritebar msg network` is called
+        let cmd_ipc = cmd.clone();
+        let mut ipc_rx = crate::ipc::subscribe();
+        crate::spawn(async move {
+            loop {
+                match ipc_rx.recv().await {
+                    Ok(msg) if msg == "network" => {
+                        let _ = tokio::process::Command::new("sh")
+                            .arg("-lc")
+                            .arg(&cmd_ipc)
+                            .spawn();
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    } else {
+        let picker = picker::build(app, bar_position, bar_height, bar_edge_margin);
+
+        let picker_click = picker.clone();
+        let container_click = container.clone();
+        gesture.connect_released(move |_, _, _, _| {
+            picker_click.toggle(container_click.upcast_ref());
+        });
+
+        // IPC: `ferritebar msg network` toggles the picker
+        let (ipc_tx, ipc_rx) = mpsc::channel::<()>(4);
+        let mut ipc_sub = crate::ipc::subscribe();
+        crate::spawn(async move {
+            loop {
+                match ipc_sub.recv().await {
+                    Ok(msg) if msg == "network" => {
+                        if ipc_tx.send(()).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        let container_ipc = container.clone();
+        super::recv_on_main_thread(ipc_rx, move |()| {
+            picker.toggle(container_ipc.upcast_ref());
+        });
+    }
+    container.add_controller(gesture);
+
+    let format = config.format.clone();
+    let tooltip_format = config.tooltip_format.clone();
+    let wifi_icons = config.wifi_icons.clone();
+
+    let container_ref = container.clone();
+    let mut buf = String::with_capacity(32);
+    let mut tooltip_buf = String::with_capacity(64);
+    super::recv_on_main_thread(rx, move |data| {
+        let icon = network_icon(&data, &wifi_icons);
+
+        buf.clear();
+        render_format(&mut buf, &format, &data, icon);
+        label.set_label(&buf);
+
+        if data.connected {
+            container_ref.remove_css_class("disconnected");
+            container_ref.add_css_class("connected");
+        } else {
+            container_ref.remove_css_class("connected");
+            container_ref.add_css_class("disconnected");
+        }
+        if data.vpn.is_some() {
+            container_ref.add_css_class("vpn");
+        } else {
+            container_ref.remove_css_class("vpn");
+        }
+
+        tooltip_buf.clear();
+        match tooltip_format {
+            Some(ref fmt) if data.connected => render_format(&mut tooltip_buf, fmt, &data, icon),
+            _ => default_tooltip(&mut tooltip_buf, &data),
+        }
+        super::set_tooltip_text(container_ref.clone(), Some(&tooltip_buf));
+    });
+
+    debug!("Network module created");
+    container.upcast()
+}
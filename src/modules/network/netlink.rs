@@ -0,0 +1,911 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::raw::c_int;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use super::{NetKind, NetworkData};
+use crate::sys::{self, poll_readable};
+
+// ---- Netlink constants (linux/netlink.h, linux/rtnetlink.h, linux/genetlink.h) ----
+
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+
+const NETLINK_ROUTE: c_int = 0;
+const NETLINK_GENERIC: c_int = 16;
+
+const RTMGRP_LINK: u32 = 0x1;
+const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+const RTMGRP_IPV4_ROUTE: u32 = 0x40;
+const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+const RTMGRP_IPV6_ROUTE: u32 = 0x400;
+
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_MULTI: u16 = 0x2;
+const NLM_F_DUMP: u16 = 0x300;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+
+const RTM_GETLINK: u16 = 18;
+const RTM_GETADDR: u16 = 22;
+const RTM_GETROUTE: u16 = 26;
+
+const IFLA_IFNAME: u16 = 3;
+const IFLA_OPERSTATE: u16 = 16;
+const IFLA_LINKINFO: u16 = 18;
+const IFLA_INFO_KIND: u16 = 1;
+const IFF_UP: u32 = 0x1;
+const IFF_LOWER_UP: u32 = 0x10000;
+const IF_OPER_DOWN: u8 = 2;
+
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const RT_SCOPE_LINK: u8 = 253;
+
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_TABLE: u16 = 15;
+const RT_TABLE_MAIN: u32 = 254;
+const RTN_UNICAST: u8 = 1;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_WIPHY_FREQ: u16 = 38;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+
+const NLMSG_HDRLEN: usize = 16;
+
+/// Watch rtnetlink link/address/route events and send a fresh snapshot after
+/// each burst of changes, plus once per `interval_secs` so Wi-Fi signal
+/// strength stays current. Runs on a blocking thread.
+///
+/// Returns `Ok(())` once the receiver is dropped, or an error if the netlink
+/// sockets cannot be opened.
+pub(super) fn watch(tx: &mpsc::Sender<NetworkData>, interval_secs: u64) -> io::Result<()> {
+    let mut events = NetlinkSocket::open(
+        NETLINK_ROUTE,
+        RTMGRP_LINK
+            | RTMGRP_IPV4_IFADDR
+            | RTMGRP_IPV4_ROUTE
+            | RTMGRP_IPV6_IFADDR
+            | RTMGRP_IPV6_ROUTE,
+    )?;
+    let mut route = NetlinkSocket::open(NETLINK_ROUTE, 0)?;
+
+    // Missing when the kernel has no cfg80211 (e.g. wired-only VMs)
+    let mut nl80211 = match Nl80211::open() {
+        Ok(nl) => Some(nl),
+        Err(e) => {
+            debug!("Network: nl80211 unavailable: {e}");
+            None
+        }
+    };
+
+    debug!("Network: using netlink backend");
+
+    let raw_fd = events.as_raw_fd();
+    let refresh = Duration::from_secs(interval_secs.max(1));
+    let mut buf = vec![0u8; 32 * 1024];
+
+    loop {
+        let data = read_state(&mut route, nl80211.as_mut())?;
+        if tx.blocking_send(data).is_err() {
+            return Ok(());
+        }
+
+        let deadline = Instant::now() + refresh;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            let timeout_ms = remaining.as_millis().min(i32::MAX as u128) as i32;
+            if poll_readable(raw_fd, timeout_ms) {
+                // Link flaps and DHCP renewals arrive as bursts; drain them
+                // all before re-reading state.
+                events.recv_event(&mut buf)?;
+                while poll_readable(raw_fd, 100) {
+                    events.recv_event(&mut buf)?;
+                }
+                break;
+            }
+        }
+    }
+}
+
+fn read_state(route: &mut NetlinkSocket, nl80211: Option<&mut Nl80211>) -> io::Result<NetworkData> {
+    let Some(default) = default_route(|family| route.dump_routes(family))? else {
+        return Ok(NetworkData::default());
+    };
+    let ifindex = default.oif;
+
+    let Some((ifname, operstate)) = read_link(route, ifindex)? else {
+        return Ok(NetworkData::default());
+    };
+    if operstate == Some(IF_OPER_DOWN) {
+        return Ok(NetworkData::default());
+    }
+
+    let mut data = NetworkData {
+        connected: true,
+        interface: ifname.into_boxed_str(),
+        kind: NetKind::Ethernet,
+        gateway: default.gateway.map(String::into_boxed_str),
+        ..NetworkData::default()
+    };
+
+    if let Some(nl) = nl80211 {
+        if let Some(wifi) = nl.interface(ifindex) {
+            data.kind = NetKind::Wifi;
+            data.ssid = wifi.ssid;
+            data.frequency = wifi.frequency;
+            data.signal = nl.station_signal(ifindex).map(dbm_to_percent);
+        }
+    }
+
+    parse_addresses(&route.dump_addresses()?, ifindex, &mut data);
+    data.vpn = vpn_link(route)?.map(String::into_boxed_str);
+
+    Ok(data)
+}
+
+struct DefaultRoute {
+    oif: u32,
+    metric: u32,
+    gateway: Option<String>,
+}
+
+/// The default route to use: the lowest-metric one from the IPv4 dump, else
+/// from the IPv6 one. `dump` returns the `RTM_NEWROUTE` payloads for a family.
+fn default_route(
+    mut dump: impl FnMut(u8) -> io::Result<Vec<Vec<u8>>>,
+) -> io::Result<Option<DefaultRoute>> {
+    for family in [AF_INET, AF_INET6] {
+        if let Some(best) = parse_default_route(family, &dump(family)?) {
+            return Ok(Some(best));
+        }
+    }
+    Ok(None)
+}
+
+/// Lowest-metric default route in the main table among `RTM_NEWROUTE`
+/// payloads of one family
+fn parse_default_route(family: u8, replies: &[Vec<u8>]) -> Option<DefaultRoute> {
+    let mut best: Option<DefaultRoute> = None;
+    for msg in replies {
+        if msg.len() < 12 {
+            continue;
+        }
+        let dst_len = msg[1];
+        let mut table = msg[4] as u32;
+        let rtm_type = msg[7];
+        if dst_len != 0 || rtm_type != RTN_UNICAST {
+            continue;
+        }
+
+        let mut oif = None;
+        let mut metric = 0;
+        let mut gateway = None;
+        for (kind, value) in Attrs(&msg[12..]) {
+            match kind {
+                RTA_OIF => oif = read_u32(value),
+                RTA_GATEWAY => gateway = format_ip(family, value),
+                RTA_PRIORITY => metric = read_u32(value).unwrap_or(0),
+                RTA_TABLE => table = read_u32(value).unwrap_or(table),
+                _ => {}
+            }
+        }
+        if table != RT_TABLE_MAIN {
+            continue;
+        }
+        if let Some(oif) = oif {
+            if best.as_ref().is_none_or(|b| metric < b.metric) {
+                best = Some(DefaultRoute {
+                    oif,
+                    metric,
+                    gateway,
+                });
+            }
+        }
+    }
+    best
+}
+
+fn read_link(sock: &mut NetlinkSocket, ifindex: u32) -> io::Result<Option<(String, Option<u8>)>> {
+    // struct ifinfomsg
+    let mut ifinfo = [0u8; 16];
+    ifinfo[4..8].copy_from_slice(&(ifindex as i32).to_ne_bytes());
+    let replies = match sock.request(RTM_GETLINK, NLM_F_REQUEST, &ifinfo) {
+        Ok(replies) => replies,
+        // The link went away between the route dump and this request
+        Err(e) if e.raw_os_error() == Some(19) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    Ok(replies.first().and_then(|msg| parse_link(msg)))
+}
+
+/// Name and operstate from an `RTM_NEWLINK` payload
+fn parse_link(msg: &[u8]) -> Option<(String, Option<u8>)> {
+    let attrs = msg.get(16..)?;
+    let mut name = None;
+    let mut operstate = None;
+    for (kind, value) in Attrs(attrs) {
+        match kind {
+            IFLA_IFNAME => name = Some(read_cstr(value)),
+            IFLA_OPERSTATE => operstate = value.first().copied(),
+            _ => {}
+        }
+    }
+    name.map(|n| (n, operstate))
+}
+
+/// Name of the first WireGuard or tun/tap link that is up. Link events on the
+/// watcher socket already cover these coming and going.
+fn vpn_link(sock: &mut NetlinkSocket) -> io::Result<Option<String>> {
+    let ifinfo = [0u8; 16];
+    let replies = sock.request(RTM_GETLINK, NLM_F_REQUEST | NLM_F_DUMP, &ifinfo)?;
+    Ok(parse_vpn_link(&replies))
+}
+
+/// Name of the first WireGuard or tun/tap link that is up among
+/// `RTM_NEWLINK` payloads
+fn parse_vpn_link(replies: &[Vec<u8>]) -> Option<String> {
+    for msg in replies {
+        if msg.len() < 16 {
+            continue;
+        }
+        let flags = read_u32(&msg[8..12]).unwrap_or(0);
+        if flags & (IFF_UP | IFF_LOWER_UP) != IFF_UP | IFF_LOWER_UP {
+            continue;
+        }
+
+        let mut name = None;
+        let mut is_vpn = false;
+        for (kind, value) in Attrs(&msg[16..]) {
+            match kind {
+                IFLA_IFNAME => name = Some(read_cstr(value)),
+                IFLA_LINKINFO => {
+                    is_vpn = Attrs(value).any(|(kind, value)| {
+                        kind == IFLA_INFO_KIND
+                            && matches!(read_cstr(value).as_str(), "wireguard" | "tun")
+                    });
+                }
+                _ => {}
+            }
+        }
+        if is_vpn {
+            return name;
+        }
+    }
+    None
+}
+
+/// One-shot [`vpn_link`] for backends that don't keep a route socket open
+pub(super) fn find_vpn_link() -> io::Result<Option<String>> {
+    let mut sock = NetlinkSocket::open(NETLINK_ROUTE, 0)?;
+    vpn_link(&mut sock)
+}
+
+/// Fill in `data`'s addresses for `ifindex` from `RTM_NEWADDR` payloads:
+/// the first IPv4 address with its prefix, and the first IPv6 address that
+/// isn't link-local
+fn parse_addresses(replies: &[Vec<u8>], ifindex: u32, data: &mut NetworkData) {
+    for msg in replies {
+        if msg.len() < 8 {
+            continue;
+        }
+        let family = msg[0];
+        let prefix = msg[1];
+        let scope = msg[3];
+        if read_u32(&msg[4..8]) != Some(ifindex) {
+            continue;
+        }
+
+        let mut address = None;
+        let mut local = None;
+        for (kind, value) in Attrs(&msg[8..]) {
+            match kind {
+                IFA_ADDRESS => address = format_ip(family, value),
+                IFA_LOCAL => local = format_ip(family, value),
+                _ => {}
+            }
+        }
+
+        // On point-to-point links IFA_ADDRESS is the peer; IFA_LOCAL is ours
+        let Some(addr) = local.or(address) else {
+            continue;
+        };
+        match family {
+            AF_INET if data.ipv4.is_none() => {
+                data.ipv4 = Some(addr.into_boxed_str());
+                data.cidr = Some(prefix);
+            }
+            AF_INET6 if data.ipv6.is_none() && scope != RT_SCOPE_LINK => {
+                data.ipv6 = Some(addr.into_boxed_str());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Map an RSSI in dBm onto 0-100 the way most Wi-Fi UIs do (-100 dBm = 0%,
+/// -50 dBm and above = 100%).
+fn dbm_to_percent(dbm: i8) -> u8 {
+    ((dbm as i32 + 100) * 2).clamp(0, 100) as u8
+}
+
+fn format_ip(family: u8, bytes: &[u8]) -> Option<String> {
+    match family {
+        AF_INET => {
+            let octets: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+            Some(std::net::Ipv4Addr::from(octets).to_string())
+        }
+        AF_INET6 => {
+            let octets: [u8; 16] = bytes.get(..16)?.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_ne_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+fn read_cstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+// ---- nl80211 (generic netlink) ----
+
+struct WifiInterface {
+    ssid: Option<Box<str>>,
+    frequency: Option<u32>,
+}
+
+struct Nl80211 {
+    sock: NetlinkSocket,
+    family: u16,
+}
+
+impl Nl80211 {
+    fn open() -> io::Result<Self> {
+        let mut sock = NetlinkSocket::open(NETLINK_GENERIC, 0)?;
+
+        let mut payload = genl_header(CTRL_CMD_GETFAMILY);
+        push_attr(&mut payload, CTRL_ATTR_FAMILY_NAME, b"nl80211\0");
+        let replies = sock.request(GENL_ID_CTRL, NLM_F_REQUEST, &payload)?;
+
+        let family = parse_family_id(&replies)
+            .ok_or_else(|| io::Error::other("nl80211 family id missing"))?;
+
+        Ok(Self { sock, family })
+    }
+
+    /// `None` if the interface is not wireless. The SSID is `None` while the
+    /// interface is not associated with a network.
+    fn interface(&mut self, ifindex: u32) -> Option<WifiInterface> {
+        let mut payload = genl_header(NL80211_CMD_GET_INTERFACE);
+        push_attr(&mut payload, NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes());
+        // Non-wireless interfaces answer with ENODEV
+        let replies = self
+            .sock
+            .request(self.family, NLM_F_REQUEST, &payload)
+            .ok()?;
+        parse_interface(&replies)
+    }
+
+    fn station_signal(&mut self, ifindex: u32) -> Option<i8> {
+        let mut payload = genl_header(NL80211_CMD_GET_STATION);
+        push_attr(&mut payload, NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes());
+        let replies = self
+            .sock
+            .request(self.family, NLM_F_REQUEST | NLM_F_DUMP, &payload)
+            .ok()?;
+        parse_station_signal(&replies)
+    }
+}
+
+/// Family id from a `CTRL_CMD_GETFAMILY` reply
+fn parse_family_id(replies: &[Vec<u8>]) -> Option<u16> {
+    let attrs = replies.first()?.get(4..)?;
+    let (_, value) = Attrs(attrs).find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)?;
+    Some(u16::from_ne_bytes(value.get(..2)?.try_into().ok()?))
+}
+
+/// SSID and frequency from an `NL80211_CMD_GET_INTERFACE` reply
+fn parse_interface(replies: &[Vec<u8>]) -> Option<WifiInterface> {
+    let attrs = replies.first()?.get(4..)?;
+    let mut wifi = WifiInterface {
+        ssid: None,
+        frequency: None,
+    };
+    for (kind, value) in Attrs(attrs) {
+        match kind {
+            NL80211_ATTR_SSID if !value.is_empty() => {
+                wifi.ssid = Some(String::from_utf8_lossy(value).into());
+            }
+            NL80211_ATTR_WIPHY_FREQ => wifi.frequency = read_u32(value),
+            _ => {}
+        }
+    }
+    Some(wifi)
+}
+
+/// Signal in dBm from an `NL80211_CMD_GET_STATION` dump. In station mode
+/// the only peer is the access point.
+fn parse_station_signal(replies: &[Vec<u8>]) -> Option<i8> {
+    let attrs = replies.first()?.get(4..)?;
+    let (_, sta_info) = Attrs(attrs).find(|(kind, _)| *kind == NL80211_ATTR_STA_INFO)?;
+    let (_, signal) = Attrs(sta_info).find(|(kind, _)| *kind == NL80211_STA_INFO_SIGNAL)?;
+    signal.first().map(|&b| b as i8)
+}
+
+fn genl_header(cmd: u8) -> Vec<u8> {
+    // struct genlmsghdr { cmd, version, reserved }
+    vec![cmd, 1, 0, 0]
+}
+
+fn push_attr(buf: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    let len = 4 + value.len();
+    buf.extend_from_slice(&(len as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(value);
+    buf.resize(buf.len() + (align4(len) - len), 0);
+}
+
+fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Iterator over `struct nlattr` TLVs in a netlink payload
+struct Attrs<'a>(&'a [u8]);
+
+impl<'a> Iterator for Attrs<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buf = self.0;
+        if buf.len() < 4 {
+            return None;
+        }
+        let len = u16::from_ne_bytes([buf[0], buf[1]]) as usize;
+        // Strip NLA_F_NESTED / NLA_F_NET_BYTEORDER
+        let kind = u16::from_ne_bytes([buf[2], buf[3]]) & 0x3fff;
+        if len < 4 || len > buf.len() {
+            return None;
+        }
+        let value = &buf[4..len];
+        self.0 = buf.get(align4(len)..).unwrap_or(&[]);
+        Some((kind, value))
+    }
+}
+
+// ---- Raw netlink socket ----
+
+/// A netlink socket driven with plain read/write
+struct NetlinkSocket {
+    file: File,
+    seq: u32,
+}
+
+impl NetlinkSocket {
+    fn open(protocol: c_int, groups: u32) -> io::Result<Self> {
+        Ok(Self {
+            file: File::from(sys::netlink_socket(protocol, groups)?),
+            seq: 0,
+        })
+    }
+
+    fn as_raw_fd(&self) -> i32 {
+        self.file.as_raw_fd()
+    }
+
+    /// `RTM_NEWROUTE` payloads for every route of `family`
+    fn dump_routes(&mut self, family: u8) -> io::Result<Vec<Vec<u8>>> {
+        // struct rtmsg
+        let mut rtmsg = [0u8; 12];
+        rtmsg[0] = family;
+        self.request(RTM_GETROUTE, NLM_F_REQUEST | NLM_F_DUMP, &rtmsg)
+    }
+
+    /// `RTM_NEWADDR` payloads for every address of both families
+    fn dump_addresses(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        // struct ifaddrmsg (AF_UNSPEC dumps both families)
+        let ifaddr = [0u8; 8];
+        self.request(RTM_GETADDR, NLM_F_REQUEST | NLM_F_DUMP, &ifaddr)
+    }
+
+    /// Send one request and collect the payloads (after `struct nlmsghdr`) of
+    /// every reply, following multipart dumps until `NLMSG_DONE`.
+    fn request(&mut self, msg_type: u16, flags: u16, payload: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+
+        let mut msg = Vec::with_capacity(NLMSG_HDRLEN + payload.len());
+        msg.extend_from_slice(&((NLMSG_HDRLEN + payload.len()) as u32).to_ne_bytes());
+        msg.extend_from_slice(&msg_type.to_ne_bytes());
+        msg.extend_from_slice(&flags.to_ne_bytes());
+        msg.extend_from_slice(&seq.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(payload);
+        // Unconnected netlink sockets send to the kernel by default
+        self.file.write_all(&msg)?;
+
+        let mut replies = Vec::new();
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            let n = self.file.read(&mut buf)?;
+            let mut rest = &buf[..n];
+            // Keep reading if this datagram held nothing for our request
+            let mut more = true;
+
+            while rest.len() >= NLMSG_HDRLEN {
+                let len = read_u32(rest).unwrap_or(0) as usize;
+                if len < NLMSG_HDRLEN || len > rest.len() {
+                    break;
+                }
+                let kind = u16::from_ne_bytes([rest[4], rest[5]]);
+                let msg_flags = u16::from_ne_bytes([rest[6], rest[7]]);
+                let msg_seq = read_u32(&rest[8..]).unwrap_or(0);
+                let body = &rest[NLMSG_HDRLEN..len];
+                rest = rest.get(align4(len)..).unwrap_or(&[]);
+
+                if msg_seq != seq {
+                    continue;
+                }
+                match kind {
+                    NLMSG_DONE => return Ok(replies),
+                    NLMSG_ERROR => {
+                        let errno = body
+                            .get(..4)
+                            .map(|b| i32::from_ne_bytes(b.try_into().unwrap_or([0; 4])))
+                            .unwrap_or(0);
+                        if errno != 0 {
+                            return Err(io::Error::from_raw_os_error(-errno));
+                        }
+                        return Ok(replies);
+                    }
+                    _ => {
+                        replies.push(body.to_vec());
+                        more = msg_flags & NLM_F_MULTI != 0;
+                    }
+                }
+            }
+
+            if !more {
+                return Ok(replies);
+            }
+        }
+    }
+
+    /// Read and discard one multicast notification. Kernel buffer overruns
+    /// (ENOBUFS) only mean events were dropped, which a full re-read covers.
+    fn recv_event(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        match self.file.read(buf) {
+            Ok(_) => Ok(()),
+            Err(e) if e.raw_os_error() == Some(sys::ENOBUFS) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// Reply payloads laid out as the kernel sends them on a little-endian machine
+#[cfg(all(test, target_endian = "little"))]
+mod tests {
+    use super::*;
+
+    // ip route: default via 192.168.1.1 dev 3 proto dhcp src 192.168.1.100 metric 600
+    const ROUTE_WIFI: &[u8] = &[
+        0x02, 0x00, 0x00, 0x00, 0xfe, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00, // RTA_TABLE 254
+        0x08, 0x00, 0x06, 0x00, 0x58, 0x02, 0x00, 0x00, // RTA_PRIORITY 600
+        0x08, 0x00, 0x07, 0x00, 0xc0, 0xa8, 0x01, 0x64, // RTA_PREFSRC
+        0x08, 0x00, 0x05, 0x00, 0xc0, 0xa8, 0x01, 0x01, // RTA_GATEWAY
+        0x08, 0x00, 0x04, 0x00, 0x03, 0x00, 0x00, 0x00, // RTA_OIF 3
+    ];
+
+    // default via 10.0.0.1 dev 2 proto static metric 100
+    const ROUTE_WIRED: &[u8] = &[
+        0x02, 0x00, 0x00, 0x00, 0xfe, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00, // RTA_TABLE 254
+        0x08, 0x00, 0x06, 0x00, 0x64, 0x00, 0x00, 0x00, // RTA_PRIORITY 100
+        0x08, 0x00, 0x05, 0x00, 0x0a, 0x00, 0x00, 0x01, // RTA_GATEWAY
+        0x08, 0x00, 0x04, 0x00, 0x02, 0x00, 0x00, 0x00, // RTA_OIF 2
+    ];
+
+    // 192.168.1.0/24 dev 3 scope link
+    const ROUTE_SUBNET: &[u8] = &[
+        0x02, 0x18, 0x00, 0x00, 0xfe, 0x02, 0xfd, 0x01, 0x00, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00, // RTA_TABLE 254
+        0x08, 0x00, 0x01, 0x00, 0xc0, 0xa8, 0x01, 0x00, // RTA_DST
+        0x08, 0x00, 0x04, 0x00, 0x03, 0x00, 0x00, 0x00, // RTA_OIF 3
+    ];
+
+    // wg-quick: default dev 5 table 51820. The rtmsg table byte only holds
+    // RT_TABLE_COMPAT for tables past 255.
+    const ROUTE_WG_TABLE: &[u8] = &[
+        0x02, 0x00, 0x00, 0x00, 0xfc, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x0f, 0x00, 0x6c, 0xca, 0x00, 0x00, // RTA_TABLE 51820
+        0x08, 0x00, 0x04, 0x00, 0x05, 0x00, 0x00, 0x00, // RTA_OIF 5
+    ];
+
+    // default via fe80::1 dev 3 proto ra metric 600
+    const ROUTE_V6: &[u8] = &[
+        0x0a, 0x00, 0x00, 0x00, 0xfe, 0x09, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00, // RTA_TABLE 254
+        0x08, 0x00, 0x06, 0x00, 0x58, 0x02, 0x00, 0x00, // RTA_PRIORITY 600
+        0x14, 0x00, 0x05, 0x00, 0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // RTA_GATEWAY
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, //
+        0x08, 0x00, 0x04, 0x00, 0x03, 0x00, 0x00, 0x00, // RTA_OIF 3
+    ];
+
+    // 192.168.1.100/24 brd 192.168.1.255 on wlan0 (index 3)
+    const ADDR_V4: &[u8] = &[
+        0x02, 0x18, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x01, 0x00, 0xc0, 0xa8, 0x01, 0x64, // IFA_ADDRESS
+        0x08, 0x00, 0x02, 0x00, 0xc0, 0xa8, 0x01, 0x64, // IFA_LOCAL
+        0x08, 0x00, 0x04, 0x00, 0xc0, 0xa8, 0x01, 0xff, // IFA_BROADCAST
+        0x0a, 0x00, 0x03, 0x00, b'w', b'l', b'a', b'n', b'0', 0x00, 0x00, 0x00, // IFA_LABEL
+    ];
+
+    // 127.0.0.1/8 scope host on lo (index 1)
+    const ADDR_LO: &[u8] = &[
+        0x02, 0x08, 0x80, 0xfe, 0x01, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x01, 0x00, 0x7f, 0x00, 0x00, 0x01, // IFA_ADDRESS
+        0x08, 0x00, 0x02, 0x00, 0x7f, 0x00, 0x00, 0x01, // IFA_LOCAL
+    ];
+
+    // fe80::5054:ff:fe12:3456/64 scope link on index 3
+    const ADDR_V6_LINK: &[u8] = &[
+        0x0a, 0x40, 0x80, 0xfd, 0x03, 0x00, 0x00, 0x00, //
+        0x14, 0x00, 0x01, 0x00, 0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // IFA_ADDRESS
+        0x50, 0x54, 0x00, 0xff, 0xfe, 0x12, 0x34, 0x56, //
+    ];
+
+    // 2001:db8::5/64 scope global on index 3
+    const ADDR_V6: &[u8] = &[
+        0x0a, 0x40, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, //
+        0x14, 0x00, 0x01, 0x00, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, // IFA_ADDRESS
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, //
+    ];
+
+    // 10.8.0.2 peer 10.8.0.1/32 on tun0 (index 5)
+    const ADDR_PEER: &[u8] = &[
+        0x02, 0x20, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, //
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x08, 0x00, 0x01, // IFA_ADDRESS
+        0x08, 0x00, 0x02, 0x00, 0x0a, 0x08, 0x00, 0x02, // IFA_LOCAL
+    ];
+
+    // wlan0 (index 3), up, operstate UP
+    const LINK_WLAN: &[u8] = &[
+        0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00, 0x43, 0x10, 0x01, 0x00, 0x00, 0x00, 0x00,
+        0x00, //
+        0x0a, 0x00, 0x03, 0x00, b'w', b'l', b'a', b'n', b'0', 0x00, 0x00, 0x00, // IFLA_IFNAME
+        0x08, 0x00, 0x04, 0x00, 0xdc, 0x05, 0x00, 0x00, // IFLA_MTU
+        0x05, 0x00, 0x10, 0x00, 0x06, 0x00, 0x00, 0x00, // IFLA_OPERSTATE
+    ];
+
+    // tun0 (index 6), administratively up without a carrier
+    const LINK_TUN_DOWN: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x91, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, //
+        0x09, 0x00, 0x03, 0x00, b't', b'u', b'n', b'0', 0x00, 0x00, 0x00, 0x00, // IFLA_IFNAME
+        0x0c, 0x00, 0x12, 0x80, // IFLA_LINKINFO (nested)
+        0x08, 0x00, 0x01, 0x00, b't', b'u', b'n', 0x00, // IFLA_INFO_KIND
+    ];
+
+    // wg0 (index 5), up
+    const LINK_WG: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0xd1, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        0x00, //
+        0x08, 0x00, 0x03, 0x00, b'w', b'g', b'0', 0x00, // IFLA_IFNAME
+        0x14, 0x00, 0x12, 0x80, // IFLA_LINKINFO (nested)
+        0x0e, 0x00, 0x01, 0x00, b'w', b'i', b'r', b'e', b'g', b'u', b'a', b'r', b'd', 0x00, 0x00,
+        0x00, // IFLA_INFO_KIND
+    ];
+
+    // CTRL_CMD_NEWFAMILY for nl80211
+    const FAMILY: &[u8] = &[
+        0x01, 0x02, 0x00, 0x00, //
+        0x0c, 0x00, 0x02, 0x00, b'n', b'l', b'8', b'0', b'2', b'1', b'1', 0x00, // FAMILY_NAME
+        0x06, 0x00, 0x01, 0x00, 0x1c, 0x00, 0x00, 0x00, // FAMILY_ID
+        0x08, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, // VERSION
+    ];
+
+    // NL80211_CMD_NEW_INTERFACE for wlan0 associated with "HomeNet" at 5180 MHz
+    const INTERFACE: &[u8] = &[
+        0x07, 0x01, 0x00, 0x00, //
+        0x08, 0x00, 0x03, 0x00, 0x03, 0x00, 0x00, 0x00, // IFINDEX
+        0x0a, 0x00, 0x04, 0x00, b'w', b'l', b'a', b'n', b'0', 0x00, 0x00, 0x00, // IFNAME
+        0x08, 0x00, 0x05, 0x00, 0x02, 0x00, 0x00, 0x00, // IFTYPE station
+        0x0b, 0x00, 0x34, 0x00, b'H', b'o', b'm', b'e', b'N', b'e', b't', 0x00, // SSID
+        0x08, 0x00, 0x26, 0x00, 0x3c, 0x14, 0x00, 0x00, // WIPHY_FREQ
+    ];
+
+    // NL80211_CMD_NEW_STATION for the access point, signal -58 dBm
+    const STATION: &[u8] = &[
+        0x13, 0x01, 0x00, 0x00, //
+        0x08, 0x00, 0x03, 0x00, 0x03, 0x00, 0x00, 0x00, // IFINDEX
+        0x0a, 0x00, 0x06, 0x00, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x00, 0x00, // MAC
+        0x14, 0x00, 0x15, 0x80, // STA_INFO (nested)
+        0x08, 0x00, 0x01, 0x00, 0x10, 0x00, 0x00, 0x00, // INACTIVE_TIME
+        0x05, 0x00, 0x07, 0x00, 0xc6, 0x00, 0x00, 0x00, // SIGNAL
+    ];
+
+    fn replies(msgs: &[&[u8]]) -> Vec<Vec<u8>> {
+        msgs.iter().map(|m| m.to_vec()).collect()
+    }
+
+    /// (oif, metric, gateway)
+    fn route(route: Option<DefaultRoute>) -> Option<(u32, u32, Option<String>)> {
+        route.map(|r| (r.oif, r.metric, r.gateway))
+    }
+
+    #[test]
+    fn lowest_metric_default_route_in_main_table() {
+        let dump = replies(&[ROUTE_SUBNET, ROUTE_WG_TABLE, ROUTE_WIFI, ROUTE_WIRED]);
+        assert_eq!(
+            route(parse_default_route(AF_INET, &dump)),
+            Some((2, 100, Some("10.0.0.1".to_string())))
+        );
+        assert_eq!(
+            route(parse_default_route(AF_INET, &replies(&[ROUTE_SUBNET]))),
+            None
+        );
+    }
+
+    /// `default_route` over recorded dumps, with the families it asked for
+    fn default_route_from(
+        v4: &[&[u8]],
+        v6: &[&[u8]],
+    ) -> (Option<(u32, u32, Option<String>)>, Vec<u8>) {
+        let mut asked = Vec::new();
+        let best = default_route(|family| {
+            asked.push(family);
+            Ok(replies(if family == AF_INET { v4 } else { v6 }))
+        })
+        .unwrap();
+        (route(best), asked)
+    }
+
+    #[test]
+    fn ipv4_default_route_wins() {
+        assert_eq!(
+            default_route_from(&[ROUTE_WIFI], &[ROUTE_V6]),
+            (
+                Some((3, 600, Some("192.168.1.1".to_string()))),
+                vec![AF_INET]
+            )
+        );
+        // Without an IPv4 default route the IPv6 one is used
+        assert_eq!(
+            default_route_from(&[ROUTE_SUBNET], &[ROUTE_V6]),
+            (
+                Some((3, 600, Some("fe80::1".to_string()))),
+                vec![AF_INET, AF_INET6]
+            )
+        );
+        assert_eq!(
+            default_route_from(&[], &[]),
+            (None, vec![AF_INET, AF_INET6])
+        );
+    }
+
+    #[test]
+    fn truncated_route_attributes() {
+        // RTA_OIF claims 8 bytes with 6 left: no interface, so no route
+        let mut cut_oif = ROUTE_WIRED.to_vec();
+        cut_oif.truncate(cut_oif.len() - 2);
+        // A three-byte gateway can't be an address
+        let mut short_gateway = ROUTE_WIRED.to_vec();
+        short_gateway[28] = 0x07;
+        // Shorter than struct rtmsg
+        let header_only = ROUTE_WIRED[..8].to_vec();
+
+        assert_eq!(
+            route(parse_default_route(AF_INET, &[cut_oif, header_only])),
+            None
+        );
+        assert_eq!(
+            route(parse_default_route(AF_INET, &[short_gateway])),
+            Some((2, 100, None))
+        );
+    }
+
+    #[test]
+    fn addresses_for_the_interface() {
+        let mut data = NetworkData::default();
+        let dump = replies(&[ADDR_LO, ADDR_V6_LINK, ADDR_V4, ADDR_V6, ADDR_PEER]);
+        parse_addresses(&dump, 3, &mut data);
+        assert_eq!(data.ipv4.as_deref(), Some("192.168.1.100"));
+        assert_eq!(data.cidr, Some(24));
+        // The link-local address is skipped
+        assert_eq!(data.ipv6.as_deref(), Some("2001:db8::5"));
+
+        // On a point-to-point link the local end is ours, not the peer
+        let mut data = NetworkData::default();
+        parse_addresses(&dump, 5, &mut data);
+        assert_eq!(data.ipv4.as_deref(), Some("10.8.0.2"));
+        assert_eq!(data.cidr, Some(32));
+        assert_eq!(data.ipv6, None);
+    }
+
+    #[test]
+    fn truncated_address_keeps_what_was_whole() {
+        // IFA_LOCAL is cut short; IFA_ADDRESS before it still counts
+        let cut = ADDR_V4[..22].to_vec();
+        let mut data = NetworkData::default();
+        parse_addresses(&[cut], 3, &mut data);
+        assert_eq!(data.ipv4.as_deref(), Some("192.168.1.100"));
+
+        let mut data = NetworkData::default();
+        parse_addresses(&[ADDR_V4[..6].to_vec()], 3, &mut data);
+        assert_eq!(data.ipv4, None);
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(parse_link(LINK_WLAN), Some(("wlan0".to_string(), Some(6))));
+        assert_eq!(parse_link(&LINK_WLAN[..12]), None);
+
+        // tun0 has no carrier, so wg0 is the VPN
+        let dump = replies(&[LINK_WLAN, LINK_TUN_DOWN, LINK_WG]);
+        assert_eq!(parse_vpn_link(&dump).as_deref(), Some("wg0"));
+        assert_eq!(parse_vpn_link(&dump[..2]), None);
+    }
+
+    #[test]
+    fn nl80211_replies() {
+        assert_eq!(parse_family_id(&replies(&[FAMILY])), Some(0x1c));
+        assert_eq!(parse_family_id(&replies(&[&FAMILY[..16]])), None);
+
+        let wifi = parse_interface(&replies(&[INTERFACE])).unwrap();
+        assert_eq!(wifi.ssid.as_deref(), Some("HomeNet"));
+        assert_eq!(wifi.frequency, Some(5180));
+        // Not associated: no SSID attribute
+        let wifi = parse_interface(&replies(&[&INTERFACE[..32]])).unwrap();
+        assert_eq!(wifi.ssid, None);
+        assert_eq!(wifi.frequency, None);
+        assert!(parse_interface(&[]).is_none());
+
+        assert_eq!(parse_station_signal(&replies(&[STATION])), Some(-58));
+        // STA_INFO claiming more than was received
+        let mut cut = STATION.to_vec();
+        cut.truncate(cut.len() - 4);
+        assert_eq!(parse_station_signal(&[cut]), None);
+    }
+
+    #[test]
+    fn signal_and_address_formatting() {
+        assert_eq!(dbm_to_percent(-58), 84);
+        assert_eq!(dbm_to_percent(-100), 0);
+        assert_eq!(dbm_to_percent(-110), 0);
+        assert_eq!(dbm_to_percent(-75), 50);
+        assert_eq!(dbm_to_percent(-30), 100);
+
+        assert_eq!(
+            format_ip(AF_INET, &[10, 0, 0, 1, 99]).as_deref(),
+            Some("10.0.0.1")
+        );
+        assert_eq!(format_ip(AF_INET, &[10, 0, 0]), None);
+        assert_eq!(format_ip(AF_INET6, &[0; 4]), None);
+        assert_eq!(format_ip(0, &[10, 0, 0, 1]), None);
+    }
+}
@@ -0,0 +1,870 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tracing::debug;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use super::picker::{AccessPointInfo, PickerEvent, PickerRequest, Security};
+use super::{NetKind, NetworkData};
+
+const NM_SERVICE: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+
+const DEVICE_TYPE_ETHERNET: u32 = 1;
+const DEVICE_TYPE_WIFI: u32 = 2;
+
+// NM80211ApFlags / NM80211ApSecurityFlags
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+
+// NMActiveConnectionState / NMActiveConnectionStateReason
+const ACTIVE_STATE_ACTIVATED: u32 = 2;
+const ACTIVE_STATE_DEACTIVATED: u32 = 4;
+const ACTIVE_REASON_NO_SECRETS: u32 = 9;
+const ACTIVE_REASON_LOGIN_FAILED: u32 = 10;
+
+/// How long the picker waits for NM to finish (or give up on) a connection
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn active_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+
+    fn delete(&self) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait ActiveConnection {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "Type")]
+    fn connection_type(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(property)]
+    fn ip4_config(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn ip6_config(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    /// `StateChanged`, which unlike the `State` property carries a reason
+    #[zbus(signal, name = "StateChanged")]
+    fn activation_state_changed(&self, state: u32, reason: u32) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Device {
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Wireless {
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+
+    #[zbus(property)]
+    fn frequency(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.IP4Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Ip4Config {
+    #[zbus(property)]
+    fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[zbus(property)]
+    fn gateway(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.IP6Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Ip6Config {
+    #[zbus(property)]
+    fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[zbus(property)]
+    fn gateway(&self) -> zbus::Result<String>;
+}
+
+/// Build an uncached proxy for an NM object. Every refresh re-reads the
+/// properties it needs, so property caching would only add GetAll round-trips
+/// and extra match rules per object.
+async fn object_proxy<'a, P>(conn: &zbus::Connection, path: OwnedObjectPath) -> zbus::Result<P>
+where
+    P: From<zbus::Proxy<'a>> + zbus::proxy::Defaults,
+{
+    zbus::proxy::Builder::<P>::new(conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+/// Watch NetworkManager over the system bus and send a fresh snapshot whenever
+/// NM reports a state or property change, plus once per `interval_secs`.
+///
+/// Returns `Ok(())` once the receiver is dropped, or an error if NM is not
+/// reachable (at startup or later), so the caller can fall back to sysfs.
+pub(super) async fn watch(tx: &mpsc::Sender<NetworkData>, interval_secs: u64) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let nm = NetworkManagerProxy::builder(&conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    // Fails with ServiceUnknown when NM isn't running
+    nm.state().await?;
+
+    // StateChanged on the manager and PropertiesChanged on every NM object
+    // (active connections, devices, access points) all live under NM_PATH.
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(NM_SERVICE)?
+        .path_namespace(NM_PATH)?
+        .build();
+    let mut signals = zbus::MessageStream::for_match_rule(rule, &conn, Some(64)).await?;
+
+    debug!("Network: using NetworkManager D-Bus backend");
+
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            msg = signals.next() => {
+                if msg.is_none() {
+                    return Err(zbus::Error::Failure("NetworkManager signal stream closed".into()));
+                }
+                // A reconnect emits dozens of property changes; coalesce them
+                while let Ok(Some(_)) =
+                    tokio::time::timeout(Duration::from_millis(150), signals.next()).await
+                {}
+            }
+        }
+
+        let data = read_state(&conn, &nm).await?;
+        if tx.send(data).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+async fn read_state(
+    conn: &zbus::Connection,
+    nm: &NetworkManagerProxy<'_>,
+) -> zbus::Result<NetworkData> {
+    // Prefer the primary connection, then any other active one (the primary
+    // can be a VPN without a Wi-Fi or Ethernet device of its own).
+    let candidates = candidates(
+        nm.primary_connection().await?,
+        nm.active_connections().await?,
+    );
+
+    let vpn = read_vpn(conn, &candidates).await;
+    for path in candidates {
+        match read_active_connection(conn, path).await {
+            Ok(Some(mut data)) => {
+                data.vpn = vpn;
+                return Ok(data);
+            }
+            Ok(None) => {}
+            // The connection can vanish between listing and reading it
+            Err(e) => debug!("Network: skipping active connection: {e}"),
+        }
+    }
+
+    Ok(NetworkData::default())
+}
+
+/// The primary connection ("/" when there is none), then the other active
+/// ones
+fn candidates(primary: OwnedObjectPath, active: Vec<OwnedObjectPath>) -> Vec<OwnedObjectPath> {
+    let mut candidates = Vec::with_capacity(active.len() + 1);
+    if primary.as_str() != "/" {
+        candidates.push(primary);
+    }
+    for path in active {
+        if !candidates.contains(&path) {
+            candidates.push(path);
+        }
+    }
+    candidates
+}
+
+/// Name of the active NM VPN or WireGuard connection. Tunnels NM doesn't
+/// manage (wg-quick, openvpn run by hand) still show up as kernel links.
+async fn read_vpn(conn: &zbus::Connection, active: &[OwnedObjectPath]) -> Option<Box<str>> {
+    for path in active {
+        let Ok(proxy) = object_proxy::<ActiveConnectionProxy>(conn, path.clone()).await else {
+            continue;
+        };
+        if let Ok("vpn" | "wireguard") = proxy.connection_type().await.as_deref() {
+            if let Ok(id) = proxy.id().await {
+                return Some(id.into_boxed_str());
+            }
+        }
+    }
+
+    // Netlink sockets block; keep them off the runtime's worker threads
+    match tokio::task::spawn_blocking(super::netlink::find_vpn_link).await {
+        Ok(Ok(link)) => link.map(String::into_boxed_str),
+        Ok(Err(e)) => {
+            debug!("Network: VPN link lookup failed: {e}");
+            None
+        }
+        Err(e) => {
+            debug!("Network: VPN link lookup panicked: {e}");
+            None
+        }
+    }
+}
+
+async fn read_active_connection(
+    conn: &zbus::Connection,
+    path: OwnedObjectPath,
+) -> zbus::Result<Option<NetworkData>> {
+    let active: ActiveConnectionProxy = object_proxy(conn, path).await?;
+    let Some(device_path) = active.devices().await?.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let device: DeviceProxy = object_proxy(conn, device_path.clone()).await?;
+    let kind = match device.device_type().await? {
+        DEVICE_TYPE_ETHERNET => NetKind::Ethernet,
+        DEVICE_TYPE_WIFI => NetKind::Wifi,
+        _ => return Ok(None),
+    };
+
+    let mut data = NetworkData {
+        connected: true,
+        interface: device.interface().await?.into_boxed_str(),
+        kind,
+        ..NetworkData::default()
+    };
+
+    if matches!(data.kind, NetKind::Wifi) {
+        let wireless: WirelessProxy = object_proxy(conn, device_path).await?;
+        let ap_path = wireless.active_access_point().await?;
+        if ap_path.as_str() != "/" {
+            let ap: AccessPointProxy = object_proxy(conn, ap_path).await?;
+            data.ssid = ssid_text(&ap.ssid().await?);
+            data.signal = Some(ap.strength().await?);
+            data.frequency = Some(ap.frequency().await?);
+        }
+        if data.ssid.is_none() {
+            // Fall back to the connection profile name
+            let id = active.id().await?;
+            if !id.is_empty() {
+                data.ssid = Some(id.into_boxed_str());
+            }
+        }
+    }
+
+    let ip4_path = active.ip4_config().await?;
+    if ip4_path.as_str() != "/" {
+        let ip4: Ip4ConfigProxy = object_proxy(conn, ip4_path).await?;
+        if let Some((address, prefix)) = first_address(&ip4.address_data().await?) {
+            data.ipv4 = Some(address);
+            data.cidr = Some(prefix);
+        }
+        data.gateway = non_empty(ip4.gateway().await?);
+    }
+
+    let ip6_path = active.ip6_config().await?;
+    if ip6_path.as_str() != "/" {
+        let ip6: Ip6ConfigProxy = object_proxy(conn, ip6_path).await?;
+        data.ipv6 = first_address(&ip6.address_data().await?).map(|(address, _)| address);
+        if data.gateway.is_none() {
+            data.gateway = non_empty(ip6.gateway().await?);
+        }
+    }
+
+    Ok(Some(data))
+}
+
+/// Pick the first non-link-local entry from an NM `AddressData` list as
+/// `(address, prefix)`.
+fn first_address(entries: &[HashMap<String, OwnedValue>]) -> Option<(Box<str>, u8)> {
+    entries.iter().find_map(|entry| {
+        let address = entry
+            .get("address")
+            .and_then(|v| <&str>::try_from(v).ok())?;
+        if address.starts_with("fe80:") {
+            return None;
+        }
+        let prefix = entry
+            .get("prefix")
+            .and_then(|v| u32::try_from(v).ok())
+            .unwrap_or(0);
+        Some((address.into(), prefix.min(128) as u8))
+    })
+}
+
+/// An SSID for display; it's raw bytes, usually but not always UTF-8
+fn ssid_text(ssid: &[u8]) -> Option<Box<str>> {
+    (!ssid.is_empty()).then(|| String::from_utf8_lossy(ssid).into())
+}
+
+fn non_empty(s: String) -> Option<Box<str>> {
+    (!s.is_empty()).then(|| s.into_boxed_str())
+}
+
+// ---- Wi-Fi picker ----
+
+/// Serve picker requests from the GTK side until the request channel closes
+pub(super) async fn run_picker(
+    mut request_rx: mpsc::Receiver<PickerRequest>,
+    event_tx: mpsc::Sender<PickerEvent>,
+) {
+    let conn = match zbus::Connection::system().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            debug!("Wi-Fi picker: system bus unavailable: {e}");
+            while request_rx.recv().await.is_some() {
+                let _ = event_tx
+                    .send(PickerEvent::Error("System D-Bus unavailable".into()))
+                    .await;
+            }
+            return;
+        }
+    };
+
+    while let Some(request) = request_rx.recv().await {
+        match request {
+            PickerRequest::Scan => {
+                match list_access_points(&conn).await {
+                    Ok(list) => {
+                        let _ = event_tx.send(PickerEvent::AccessPoints(list)).await;
+                    }
+                    Err(e) => {
+                        let _ = event_tx.send(PickerEvent::Error(picker_error(&e))).await;
+                        continue;
+                    }
+                }
+
+                // Show cached results right away, then refresh once the
+                // scan NM just started has had time to finish.
+                if let Err(e) = request_scan(&conn).await {
+                    // NM rejects scans requested too soon after the last one
+                    debug!("Wi-Fi picker: scan request failed: {e}");
+                }
+                let conn = conn.clone();
+                let event_tx = event_tx.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(3)).await;
+                    if let Ok(list) = list_access_points(&conn).await {
+                        let _ = event_tx.send(PickerEvent::AccessPoints(list)).await;
+                    }
+                });
+            }
+            PickerRequest::Connect {
+                ssid,
+                security,
+                password,
+            } => {
+                // Activation can take a while; keep serving scans meanwhile
+                let conn = conn.clone();
+                let event_tx = event_tx.clone();
+                tokio::spawn(async move {
+                    let event = match connect(&conn, &ssid, security, password).await {
+                        Ok(()) => PickerEvent::Activated,
+                        Err(e) => PickerEvent::Error(format!("Connecting to {ssid} failed: {e}")),
+                    };
+                    let _ = event_tx.send(event).await;
+                });
+            }
+        }
+    }
+}
+
+fn picker_error(e: &zbus::Error) -> String {
+    match e {
+        zbus::Error::MethodError(name, _, _)
+            if name.as_str() == "org.freedesktop.DBus.Error.ServiceUnknown" =>
+        {
+            "NetworkManager is not running".to_string()
+        }
+        _ => e.to_string(),
+    }
+}
+
+async fn manager(conn: &zbus::Connection) -> zbus::Result<NetworkManagerProxy<'static>> {
+    NetworkManagerProxy::builder(conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+async fn wifi_device(conn: &zbus::Connection) -> zbus::Result<Option<OwnedObjectPath>> {
+    for path in manager(conn).await?.get_devices().await? {
+        let device: DeviceProxy = object_proxy(conn, path.clone()).await?;
+        if device.device_type().await? == DEVICE_TYPE_WIFI {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+async fn request_scan(conn: &zbus::Connection) -> zbus::Result<()> {
+    if let Some(path) = wifi_device(conn).await? {
+        let wireless: WirelessProxy = object_proxy(conn, path).await?;
+        wireless.request_scan(HashMap::new()).await?;
+    }
+    Ok(())
+}
+
+/// Map SSID -> saved connection profile for every Wi-Fi profile NM knows
+async fn known_networks(
+    conn: &zbus::Connection,
+) -> zbus::Result<HashMap<Vec<u8>, OwnedObjectPath>> {
+    let settings = SettingsProxy::builder(conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let mut known = HashMap::new();
+    for path in settings.list_connections().await? {
+        let profile: SettingsConnectionProxy = object_proxy(conn, path.clone()).await?;
+        let Ok(values) = profile.get_settings().await else {
+            continue;
+        };
+        let ssid = values
+            .get("802-11-wireless")
+            .and_then(|wireless| wireless.get("ssid"))
+            .and_then(|v| Vec::<u8>::try_from(v.try_clone().ok()?).ok());
+        if let Some(ssid) = ssid {
+            known.insert(ssid, path);
+        }
+    }
+    Ok(known)
+}
+
+fn ap_security(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Security {
+    let key_mgmt = wpa_flags | rsn_flags;
+    if key_mgmt & AP_SEC_KEY_MGMT_802_1X != 0 {
+        Security::Enterprise
+    } else if key_mgmt & AP_SEC_KEY_MGMT_PSK != 0 {
+        Security::Psk
+    } else if key_mgmt & AP_SEC_KEY_MGMT_SAE != 0 {
+        Security::Sae
+    } else if flags & AP_FLAGS_PRIVACY != 0 && key_mgmt == 0 {
+        Security::Wep
+    } else {
+        Security::Open
+    }
+}
+
+/// Scanned access points, one entry per SSID (strongest wins), with the
+/// active network first and the rest by signal strength.
+async fn list_access_points(conn: &zbus::Connection) -> zbus::Result<Vec<AccessPointInfo>> {
+    let Some(device_path) = wifi_device(conn).await? else {
+        return Err(zbus::Error::Failure("No Wi-Fi device".into()));
+    };
+    let wireless: WirelessProxy = object_proxy(conn, device_path).await?;
+    let active_ap = wireless.active_access_point().await?;
+    let known = known_networks(conn).await?;
+
+    let mut scanned = Vec::new();
+    for path in wireless.get_all_access_points().await? {
+        let active = path == active_ap;
+        let ap: AccessPointProxy = object_proxy(conn, path).await?;
+        let ssid = ap.ssid().await?;
+        // Hidden networks broadcast an empty SSID
+        if ssid.is_empty() {
+            continue;
+        }
+
+        let info = AccessPointInfo {
+            ssid: String::from_utf8_lossy(&ssid).into_owned(),
+            strength: ap.strength().await?,
+            security: ap_security(
+                ap.flags().await?,
+                ap.wpa_flags().await?,
+                ap.rsn_flags().await?,
+            ),
+            active,
+            known: known.contains_key(&ssid),
+        };
+        scanned.push((ssid, info));
+    }
+    Ok(merge_access_points(scanned))
+}
+
+/// One entry per SSID from scanned `(ssid, info)` pairs, active if any of its
+/// access points is and as strong as the strongest, sorted for the picker
+fn merge_access_points(scanned: Vec<(Vec<u8>, AccessPointInfo)>) -> Vec<AccessPointInfo> {
+    let mut by_ssid: HashMap<Vec<u8>, AccessPointInfo> = HashMap::new();
+    for (ssid, info) in scanned {
+        match by_ssid.get_mut(&ssid) {
+            Some(existing) => {
+                existing.active |= info.active;
+                existing.strength = existing.strength.max(info.strength);
+            }
+            None => {
+                by_ssid.insert(ssid, info);
+            }
+        }
+    }
+
+    let mut list: Vec<AccessPointInfo> = by_ssid.into_values().collect();
+    list.sort_by(|a, b| b.active.cmp(&a.active).then(b.strength.cmp(&a.strength)));
+    list
+}
+
+async fn connect(
+    conn: &zbus::Connection,
+    ssid: &str,
+    security: Security,
+    password: Option<String>,
+) -> zbus::Result<()> {
+    let nm = manager(conn).await?;
+    let Some(device) = wifi_device(conn).await? else {
+        return Err(zbus::Error::Failure("No Wi-Fi device".into()));
+    };
+    let root = ObjectPath::from_static_str_unchecked("/");
+
+    // Saved profiles already carry their secrets
+    if let Some(profile) = known_networks(conn).await?.get(ssid.as_bytes()) {
+        let active = nm.activate_connection(profile, &device, &root).await?;
+        return wait_activated(conn, active).await;
+    }
+
+    // Let NM fill in the rest of the profile from the strongest matching AP
+    let wireless: WirelessProxy = object_proxy(conn, device.clone()).await?;
+    let mut best: Option<(u8, OwnedObjectPath)> = None;
+    for path in wireless.get_all_access_points().await? {
+        let ap: AccessPointProxy = object_proxy(conn, path.clone()).await?;
+        if ap.ssid().await? != ssid.as_bytes() {
+            continue;
+        }
+        let strength = ap.strength().await?;
+        if best.as_ref().is_none_or(|(s, _)| strength > *s) {
+            best = Some((strength, path));
+        }
+    }
+    let Some((_, ap_path)) = best else {
+        return Err(zbus::Error::Failure(format!("{ssid} is out of range")));
+    };
+
+    let mut settings = HashMap::new();
+    if let Some(psk) = password {
+        let key_mgmt = match security {
+            Security::Sae => "sae",
+            _ => "wpa-psk",
+        };
+        let mut wireless_security = HashMap::new();
+        wireless_security.insert("key-mgmt", Value::from(key_mgmt));
+        wireless_security.insert("psk", Value::from(psk));
+        settings.insert("802-11-wireless-security", wireless_security);
+    }
+
+    let (profile, active) = nm
+        .add_and_activate_connection(settings, &device, &ap_path)
+        .await?;
+    if let Err(e) = wait_activated(conn, active).await {
+        // Don't keep a profile with a wrong password: the picker would treat
+        // the network as known and never ask again
+        match object_proxy::<SettingsConnectionProxy>(conn, profile).await {
+            Ok(profile) => {
+                if let Err(e) = profile.delete().await {
+                    debug!("Wi-Fi picker: removing failed profile: {e}");
+                }
+            }
+            Err(e) => debug!("Wi-Fi picker: removing failed profile: {e}"),
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Wait until NM has activated the connection, or report why it gave up
+async fn wait_activated(conn: &zbus::Connection, path: OwnedObjectPath) -> zbus::Result<()> {
+    let active: ActiveConnectionProxy = object_proxy(conn, path).await?;
+    // Subscribe before reading the state so no change slips in between
+    let mut changes = active.receive_activation_state_changed().await?;
+    let mut state = active.state().await?;
+    let mut reason = 0;
+
+    let settled = async {
+        loop {
+            match state {
+                ACTIVE_STATE_ACTIVATED => return Ok(()),
+                ACTIVE_STATE_DEACTIVATED => {
+                    return Err(zbus::Error::Failure(deactivation_reason(reason)));
+                }
+                _ => {}
+            }
+            let Some(signal) = changes.next().await else {
+                return Err(zbus::Error::Failure(
+                    "NetworkManager dropped the connection".into(),
+                ));
+            };
+            let args = signal.args()?;
+            state = args.state;
+            reason = args.reason;
+        }
+    };
+    tokio::time::timeout(ACTIVATION_TIMEOUT, settled)
+        .await
+        .map_err(|_| zbus::Error::Failure("timed out".into()))?
+}
+
+/// Why NM gave up on a connection, from its NMActiveConnectionStateReason
+fn deactivation_reason(reason: u32) -> String {
+    match reason {
+        ACTIVE_REASON_NO_SECRETS | ACTIVE_REASON_LOGIN_FAILED => {
+            "the password was rejected".to_string()
+        }
+        _ => format!("NetworkManager deactivated it (reason {reason})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(path).unwrap()
+    }
+
+    /// One `AddressData` entry as NM sends it
+    fn address(address: &str, prefix: u32) -> HashMap<String, OwnedValue> {
+        HashMap::from([
+            (
+                "address".to_string(),
+                Value::from(address).try_to_owned().unwrap(),
+            ),
+            (
+                "prefix".to_string(),
+                Value::from(prefix).try_to_owned().unwrap(),
+            ),
+        ])
+    }
+
+    fn ap(ssid: &str, strength: u8, active: bool) -> (Vec<u8>, AccessPointInfo) {
+        let info = AccessPointInfo {
+            ssid: ssid.to_string(),
+            strength,
+            security: Security::Psk,
+            active,
+            known: false,
+        };
+        (ssid.as_bytes().to_vec(), info)
+    }
+
+    #[test]
+    fn primary_connection_comes_first() {
+        let active = vec![
+            path("/org/freedesktop/NetworkManager/ActiveConnection/3"),
+            path("/org/freedesktop/NetworkManager/ActiveConnection/5"),
+        ];
+        assert_eq!(
+            candidates(
+                path("/org/freedesktop/NetworkManager/ActiveConnection/5"),
+                active.clone()
+            ),
+            [active[1].clone(), active[0].clone()]
+        );
+        assert_eq!(candidates(path("/"), active.clone()), active);
+        assert!(candidates(path("/"), Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn reads_ssid_bytes() {
+        assert_eq!(ssid_text(b"Home").as_deref(), Some("Home"));
+        assert_eq!(
+            ssid_text("Caf\u{e9}".as_bytes()).as_deref(),
+            Some("Caf\u{e9}")
+        );
+        assert_eq!(ssid_text(b"Bad\xff").as_deref(), Some("Bad\u{fffd}"));
+        // Hidden networks
+        assert_eq!(ssid_text(b""), None);
+    }
+
+    #[test]
+    fn picks_first_routable_address() {
+        let ipv4 = [address("192.168.1.23", 24), address("10.8.0.2", 32)];
+        assert_eq!(first_address(&ipv4), Some(("192.168.1.23".into(), 24)));
+
+        let ipv6 = [
+            address("fe80::1c2b:3aff:fe4d:5e6f", 64),
+            address("2001:db8::23", 64),
+        ];
+        assert_eq!(first_address(&ipv6), Some(("2001:db8::23".into(), 64)));
+        assert_eq!(first_address(&ipv6[..1]), None);
+        assert_eq!(first_address(&[]), None);
+
+        // A missing prefix reads as 0 rather than dropping the address
+        let bare = HashMap::from([(
+            "address".to_string(),
+            Value::from("192.168.1.23").try_to_owned().unwrap(),
+        )]);
+        assert_eq!(first_address(&[bare]), Some(("192.168.1.23".into(), 0)));
+    }
+
+    #[test]
+    fn classifies_security() {
+        assert_eq!(ap_security(0, 0, 0), Security::Open);
+        assert_eq!(ap_security(AP_FLAGS_PRIVACY, 0, 0), Security::Wep);
+        assert_eq!(
+            ap_security(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_PSK),
+            Security::Psk
+        );
+        assert_eq!(
+            ap_security(AP_FLAGS_PRIVACY, AP_SEC_KEY_MGMT_PSK, 0),
+            Security::Psk
+        );
+        assert_eq!(
+            ap_security(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_SAE),
+            Security::Sae
+        );
+        // WPA2/WPA3 transition networks accept a PSK
+        assert_eq!(
+            ap_security(
+                AP_FLAGS_PRIVACY,
+                0,
+                AP_SEC_KEY_MGMT_PSK | AP_SEC_KEY_MGMT_SAE
+            ),
+            Security::Psk
+        );
+        assert_eq!(
+            ap_security(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_802_1X),
+            Security::Enterprise
+        );
+    }
+
+    #[test]
+    fn merges_access_points_per_ssid() {
+        let list = merge_access_points(vec![
+            ap("Office", 40, false),
+            ap("Home", 55, false),
+            ap("Office", 80, false),
+            ap("Home", 30, true),
+            ap("Cafe", 60, false),
+        ]);
+        let summary: Vec<_> = list
+            .iter()
+            .map(|ap| (ap.ssid.as_str(), ap.strength, ap.active))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Home", 55, true),
+                ("Office", 80, false),
+                ("Cafe", 60, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn explains_deactivation() {
+        assert_eq!(
+            deactivation_reason(ACTIVE_REASON_NO_SECRETS),
+            "the password was rejected"
+        );
+        assert_eq!(
+            deactivation_reason(ACTIVE_REASON_LOGIN_FAILED),
+            "the password was rejected"
+        );
+        assert_eq!(
+            deactivation_reason(3),
+            "NetworkManager deactivated it (reason 3)"
+        );
+    }
+}
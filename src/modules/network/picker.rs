@@ -0,0 +1,269 @@
+use gtk::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::config::types::Position;
+use crate::widgets::popup_menu::PopupMenu;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Security {
+    Open,
+    Wep,
+    Psk,
+    Sae,
+    Enterprise,
+}
+
+impl Security {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Open => "",
+            Self::Wep => "WEP",
+            Self::Psk => "WPA",
+            Self::Sae => "WPA3",
+            Self::Enterprise => "802.1X",
+        }
+    }
+
+    /// Whether a new profile can be created from just a password
+    fn is_supported(self) -> bool {
+        matches!(self, Self::Open | Self::Psk | Self::Sae)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct AccessPointInfo {
+    pub(super) ssid: String,
+    pub(super) strength: u8,
+    pub(super) security: Security,
+    pub(super) active: bool,
+    /// A saved connection profile exists for this SSID
+    pub(super) known: bool,
+}
+
+/// Requests from the GTK picker to the NetworkManager task
+#[derive(Debug)]
+pub(super) enum PickerRequest {
+    Scan,
+    Connect {
+        ssid: String,
+        security: Security,
+        password: Option<String>,
+    },
+}
+
+/// Results from the NetworkManager task back to GTK
+#[derive(Debug)]
+pub(super) enum PickerEvent {
+    AccessPoints(Vec<AccessPointInfo>),
+    Activated,
+    Error(String),
+}
+
+/// Popup listing scanned Wi-Fi networks, opened from the network module
+pub(super) struct WifiPicker {
+    popup: PopupMenu,
+    request_tx: mpsc::Sender<PickerRequest>,
+    /// Scan results must not replace an open password prompt
+    prompting: Cell<bool>,
+}
+
+pub(super) fn build(
+    app: &gtk::Application,
+    bar_position: Position,
+    bar_height: u32,
+    bar_edge_margin: i32,
+) -> Rc<WifiPicker> {
+    let (request_tx, request_rx) = mpsc::channel::<PickerRequest>(8);
+    let (event_tx, event_rx) = mpsc::channel::<PickerEvent>(8);
+
+    crate::spawn(super::nm::run_picker(request_rx, event_tx));
+
+    // Keyboard mode is on demand, so the password entry can take focus
+    let popup = PopupMenu::new(
+        app,
+        "ferritebar-wifi-picker",
+        bar_position,
+        bar_height,
+        bar_edge_margin,
+    );
+    popup.set_width(260);
+    popup.add_css_class("wifi-picker");
+
+    let picker = Rc::new(WifiPicker {
+        popup,
+        request_tx,
+        prompting: Cell::new(false),
+    });
+
+    let picker_ref = picker.clone();
+    crate::modules::recv_on_main_thread(event_rx, move |event| {
+        // Late results for a dismissed popup are dropped
+        if !picker_ref.popup.is_visible() {
+            return;
+        }
+        match event {
+            PickerEvent::AccessPoints(list) => {
+                if !picker_ref.prompting.get() {
+                    picker_ref.show_list(&list);
+                }
+            }
+            PickerEvent::Activated => picker_ref.popup.hide(),
+            PickerEvent::Error(msg) => {
+                picker_ref.prompting.set(false);
+                picker_ref.show_message(&msg);
+            }
+        }
+    });
+
+    picker
+}
+
+impl WifiPicker {
+    /// Open the popup under `anchor` and start a scan, or close it if open
+    pub(super) fn toggle(self: &Rc<Self>, anchor: &gtk::Widget) {
+        if self.popup.is_visible() {
+            self.popup.hide();
+            return;
+        }
+
+        self.prompting.set(false);
+        self.show_message("Scanning\u{2026}");
+        self.popup.popup_at(anchor);
+        let _ = self.request_tx.try_send(PickerRequest::Scan);
+    }
+
+    fn show_message(&self, text: &str) {
+        self.popup.clear();
+        self.popup.add_header(text);
+    }
+
+    fn show_list(self: &Rc<Self>, list: &[AccessPointInfo]) {
+        if list.is_empty() {
+            self.show_message("No networks found");
+            return;
+        }
+
+        self.popup.clear();
+        for ap in list {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+
+            let bars = gtk::Label::new(Some(signal_bars(ap.strength)));
+            bars.add_css_class("wifi-signal");
+            row.append(&bars);
+
+            let ssid = gtk::Label::new(Some(&ap.ssid));
+            ssid.set_halign(gtk::Align::Start);
+            ssid.set_hexpand(true);
+            row.append(&ssid);
+
+            if ap.security != Security::Open {
+                let security = gtk::Label::new(Some(ap.security.label()));
+                security.add_css_class("wifi-security");
+                row.append(&security);
+            }
+
+            let btn = gtk::Button::new();
+            btn.set_child(Some(&row));
+            btn.set_halign(gtk::Align::Fill);
+            if ap.active {
+                btn.add_css_class("toggle-on");
+                btn.add_css_class("active");
+            }
+            if !ap.known && !ap.security.is_supported() {
+                btn.set_sensitive(false);
+                crate::modules::set_tooltip_text(
+                    btn.clone(),
+                    Some(&format!(
+                        "{} networks are not supported here",
+                        ap.security.label()
+                    )),
+                );
+            }
+
+            let picker = self.clone();
+            let ap = ap.clone();
+            btn.connect_clicked(move |_| picker.select(&ap));
+
+            self.popup.append(&btn);
+        }
+    }
+
+    fn select(self: &Rc<Self>, ap: &AccessPointInfo) {
+        if ap.active {
+            self.popup.hide();
+        } else if ap.known || ap.security == Security::Open {
+            self.connect(ap, None);
+        } else {
+            self.prompt_password(ap);
+        }
+    }
+
+    fn connect(&self, ap: &AccessPointInfo, password: Option<String>) {
+        debug!("Wi-Fi picker: connecting to {}", ap.ssid);
+        self.show_message(&format!("Connecting to {}\u{2026}", ap.ssid));
+        let _ = self.request_tx.try_send(PickerRequest::Connect {
+            ssid: ap.ssid.clone(),
+            security: ap.security,
+            password,
+        });
+    }
+
+    fn prompt_password(self: &Rc<Self>, ap: &AccessPointInfo) {
+        self.prompting.set(true);
+        self.popup.clear();
+        self.popup.add_header(&format!("Password for {}", ap.ssid));
+
+        let entry = gtk::PasswordEntry::new();
+        entry.set_show_peek_icon(true);
+        entry.add_css_class("wifi-password");
+        self.popup.append(&entry);
+
+        let actions = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        actions.set_halign(gtk::Align::End);
+        let cancel = gtk::Button::with_label("Cancel");
+        let connect = gtk::Button::with_label("Connect");
+        actions.append(&cancel);
+        actions.append(&connect);
+        self.popup.append(&actions);
+
+        let submit = {
+            let picker = self.clone();
+            let ap = ap.clone();
+            let entry = entry.clone();
+            move || {
+                let password = entry.text().to_string();
+                if password.is_empty() {
+                    return;
+                }
+                picker.prompting.set(false);
+                picker.connect(&ap, Some(password));
+            }
+        };
+        let submit = Rc::new(submit);
+
+        let submit_entry = submit.clone();
+        entry.connect_activate(move |_| submit_entry());
+        connect.connect_clicked(move |_| submit());
+
+        let picker = self.clone();
+        cancel.connect_clicked(move |_| {
+            picker.prompting.set(false);
+            picker.show_message("Scanning\u{2026}");
+            let _ = picker.request_tx.try_send(PickerRequest::Scan);
+        });
+
+        entry.grab_focus();
+    }
+}
+
+fn signal_bars(strength: u8) -> &'static str {
+    match strength {
+        75.. => "\u{2582}\u{2584}\u{2586}\u{2588}",
+        50..=74 => "\u{2582}\u{2584}\u{2586}_",
+        25..=49 => "\u{2582}\u{2584}__",
+        _ => "\u{2582}___",
+    }
+}
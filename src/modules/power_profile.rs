@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use gtk::prelude::*;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedValue;
+
+use crate::config::types::PowerProfileConfig;
+
+/// power-profiles-daemon 0.20 moved under UPower's name but still answers on
+/// the original one; try the new name first.
+const SERVICES: [(&str, &str); 2] = [
+    (
+        "org.freedesktop.UPower.PowerProfiles",
+        "/org/freedesktop/UPower/PowerProfiles",
+    ),
+    ("net.hadess.PowerProfiles", "/net/hadess/PowerProfiles"),
+];
+
+/// How long to wait before looking for the daemon again
+const RETRY: Duration = Duration::from_secs(30);
+
+/// The same interface is published under both service names, so the proxy's
+/// destination, path and interface are filled in from `SERVICES`.
+#[zbus::proxy(interface = "net.hadess.PowerProfiles")]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    /// Comma-separated reasons, empty when performance runs at full speed
+    #[zbus(property)]
+    fn performance_degraded(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn active_profile_holds(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+#[derive(Debug)]
+struct ProfileState {
+    active: String,
+    degraded: String,
+    holds: Vec<Hold>,
+}
+
+/// An application keeping a profile active (e.g. a game holding performance)
+#[derive(Debug)]
+struct Hold {
+    application: String,
+    profile: String,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ProfileRequest {
+    /// Next profile, wrapping around
+    Cycle,
+    /// One step towards performance (positive) or power-saver (negative)
+    Step(i32),
+}
+
+/// Daemon profile name to display name and Font Awesome glyph
+fn describe(profile: &str) -> (&str, &'static str) {
+    match profile {
+        "performance" => ("Performance", "\u{f625}"), // fa-gauge-high
+        "balanced" => ("Balanced", "\u{f24e}"),       // fa-scale-balanced
+        "power-saver" => ("Power saver", "\u{f06c}"), // fa-leaf
+        other => (other, "\u{f0e7}"),                 // fa-bolt
+    }
+}
+
+fn describe_degradation(reason: &str) -> &str {
+    match reason {
+        "lap-detected" => "the computer is on a lap",
+        "high-operating-temperature" => "high operating temperature",
+        other => other,
+    }
+}
+
+async fn open_proxy(conn: &zbus::Connection) -> zbus::Result<PowerProfilesProxy<'static>> {
+    let mut last_err = None;
+    for (service, path) in SERVICES {
+        let proxy = PowerProfilesProxy::builder(conn)
+            .destination(service)?
+            .path(path)?
+            .interface(service)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        // Fails with ServiceUnknown when the daemon isn't running
+        match proxy.active_profile().await {
+            Ok(_) => return Ok(proxy),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| zbus::Error::Failure("no power profile service".into())))
+}
+
+fn entry_str(entry: &HashMap<String, OwnedValue>, key: &str) -> String {
+    entry
+        .get(key)
+        .and_then(|v| <&str>::try_from(v).ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+async fn read_state(proxy: &PowerProfilesProxy<'_>) -> zbus::Result<ProfileState> {
+    let holds = proxy
+        .active_profile_holds()
+        .await?
+        .iter()
+        .map(|entry| Hold {
+            application: entry_str(entry, "ApplicationId"),
+            profile: entry_str(entry, "Profile"),
+            reason: entry_str(entry, "Reason"),
+        })
+        .collect();
+
+    Ok(ProfileState {
+        active: proxy.active_profile().await?,
+        // Not every driver reports degradation
+        degraded: proxy.performance_degraded().await.unwrap_or_default(),
+        holds,
+    })
+}
+
+/// Switch profiles relative to the active one
+async fn apply(proxy: &PowerProfilesProxy<'_>, request: ProfileRequest) -> zbus::Result<()> {
+    let profiles: Vec<String> = proxy
+        .profiles()
+        .await?
+        .iter()
+        .map(|entry| entry_str(entry, "Profile"))
+        .filter(|name| !name.is_empty())
+        .collect();
+    let active = proxy.active_profile().await?;
+    if let Some(next) = next_profile(&profiles, &active, request) {
+        proxy.set_active_profile(next).await?;
+    }
+    Ok(())
+}
+
+/// The profile `request` switches to, if it changes anything. The daemon
+/// lists profiles from power-saver to performance; power-saver and balanced
+/// always exist.
+fn next_profile<'a>(
+    profiles: &'a [String],
+    active: &str,
+    request: ProfileRequest,
+) -> Option<&'a str> {
+    let last = profiles.len().checked_sub(1)?;
+    let current = profiles.iter().position(|p| p == active).unwrap_or(0);
+    let next = match request {
+        // Click order: performance, balanced, power-saver, performance, ...
+        ProfileRequest::Cycle => current.checked_sub(1).unwrap_or(last),
+        ProfileRequest::Step(delta) => current.saturating_add_signed(delta as isize).min(last),
+    };
+    (next != current).then(|| profiles[next].as_str())
+}
+
+/// Follow the active profile and apply requests from the widget.
+///
+/// Returns `Ok(())` once the receiver is dropped, or an error if the daemon
+/// is not reachable so the caller can retry later.
+async fn watch(
+    tx: &mpsc::Sender<Option<ProfileState>>,
+    request_rx: &mut mpsc::Receiver<ProfileRequest>,
+) -> zbus::Result<()> {
+    let conn = zbus::Connection::system().await?;
+    let proxy = open_proxy(&conn).await?;
+
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(proxy.inner().destination().to_owned())?
+        .path(proxy.inner().path().to_owned())?
+        .build();
+    let mut signals = zbus::MessageStream::for_match_rule(rule, &conn, Some(16)).await?;
+
+    debug!(
+        "Power profile: using {}",
+        proxy.inner().destination().as_str()
+    );
+
+    loop {
+        let state = read_state(&proxy).await?;
+        if tx.send(Some(state)).await.is_err() {
+            return Ok(());
+        }
+
+        // A successful switch comes back as PropertiesChanged, so requests
+        // don't trigger a refresh of their own
+        loop {
+            tokio::select! {
+                msg = signals.next() => {
+                    if msg.is_none() {
+                        return Err(zbus::Error::Failure("power profile signal stream closed".into()));
+                    }
+                    while let Ok(Some(_)) =
+                        tokio::time::timeout(Duration::from_millis(150), signals.next()).await
+                    {}
+                    break;
+                }
+                request = request_rx.recv() => {
+                    let Some(request) = request else {
+                        return Ok(());
+                    };
+                    // Switching needs polkit authorization on some setups
+                    if let Err(e) = apply(&proxy, request).await {
+                        warn!("Failed to switch power profile: {e}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn format_tooltip(buf: &mut String, state: &ProfileState) {
+    let (name, _) = describe(&state.active);
+    let _ = write!(buf, "Power profile: {name}");
+    for reason in state.degraded.split(',').filter(|r| !r.is_empty()) {
+        let _ = write!(
+            buf,
+            "\nPerformance degraded: {}",
+            describe_degradation(reason.trim())
+        );
+    }
+    for hold in &state.holds {
+        let (profile, _) = describe(&hold.profile);
+        let _ = write!(buf, "\n{profile} held by {}", hold.application);
+        if !hold.reason.is_empty() {
+            let _ = write!(buf, ": {}", hold.reason);
+        }
+    }
+}
+
+pub fn build(config: &PowerProfileConfig) -> gtk::Widget {
+    let (tx, rx) = mpsc::channel::<Option<ProfileState>>(8);
+    let (request_tx, mut request_rx) = mpsc::channel::<ProfileRequest>(8);
+
+    crate::spawn(async move {
+        loop {
+            match watch(&tx, &mut request_rx).await {
+                Ok(()) => return,
+                Err(e) => debug!("Power profiles daemon unavailable: {e}"),
+            }
+            if tx.send(None).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(RETRY).await;
+            // Drop clicks made while there was nothing to switch
+            while request_rx.try_recv().is_ok() {}
+        }
+    });
+
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    container.add_css_class("module");
+    container.add_css_class("power-profile");
+
+    let label = gtk::Label::new(None);
+    label.add_css_class("module-label");
+    container.append(&label);
+
+    // Click handler: run on_click if set, otherwise cycle profiles
+    let gesture = gtk::GestureClick::new();
+    if let Some(ref cmd) = config.on_click {
+        let on_click = cmd.clone();
+        gesture.connect_released(move |_, _, _, _| {
+            let cmd = on_click.clone();
+            crate::spawn(async move {
+                let _ = tokio::process::Command::new("sh")
+                    .arg("-lc")
+                    .arg(&cmd)
+                    .spawn();
+            });
+        });
+    } else {
+        let tx = request_tx.clone();
+        gesture.connect_released(move |_, _, _, _| {
+            let tx = tx.clone();
+            glib::spawn_future_local(async move {
+                let _ = tx.send(ProfileRequest::Cycle).await;
+            });
+        });
+    }
+    container.add_controller(gesture);
+
+    // Scroll up for more performance, down for more power saving. Discrete
+    // steps, so a touchpad swipe switches once rather than per small delta.
+    let scroll = gtk::EventControllerScroll::new(
+        gtk::EventControllerScrollFlags::VERTICAL | gtk::EventControllerScrollFlags::DISCRETE,
+    );
+    scroll.connect_scroll(move |_, _dx, dy| {
+        if dy == 0.0 {
+            return glib::Propagation::Proceed;
+        }
+        let step = if dy > 0.0 { -1 } else { 1 };
+        let tx = request_tx.clone();
+        glib::spawn_future_local(async move {
+            let _ = tx.send(ProfileRequest::Step(step)).await;
+        });
+        glib::Propagation::Stop
+    });
+    container.add_controller(scroll);
+
+    let format = config.format.clone();
+
+    let container_ref = container.clone();
+    let mut active_class = String::new();
+    let mut buf = String::with_capacity(32);
+    let mut tooltip_buf = String::with_capacity(128);
+    super::recv_on_main_thread(rx, move |state| {
+        if !active_class.is_empty() {
+            container_ref.remove_css_class(&active_class);
+            active_class.clear();
+        }
+
+        // Hidden until the daemon shows up
+        let Some(state) = state else {
+            container_ref.set_visible(false);
+            return;
+        };
+        container_ref.set_visible(true);
+
+        // "performance", "balanced" or "power-saver"
+        if !state.active.is_empty() {
+            active_class.push_str(&state.active);
+            container_ref.add_css_class(&active_class);
+        }
+        if state.degraded.is_empty() {
+            container_ref.remove_css_class("degraded");
+        } else {
+            container_ref.add_css_class("degraded");
+        }
+
+        let (name, icon) = describe(&state.active);
+        buf.clear();
+        for part in format.split('{') {
+            let Some((key, rest)) = part.split_once('}') else {
+                buf.push_str(part);
+                continue;
+            };
+            match key {
+                "icon" => buf.push_str(icon),
+                "profile" => buf.push_str(name),
+                _ => {
+                    buf.push_str(part);
+                    continue;
+                }
+            }
+            buf.push_str(rest);
+        }
+        label.set_label(&buf);
+
+        tooltip_buf.clear();
+        format_tooltip(&mut tooltip_buf, &state);
+        super::set_tooltip_text(container_ref.clone(), Some(&tooltip_buf));
+    });
+
+    debug!("Power profile module created");
+    container.upcast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn cycles_towards_power_saving_and_wraps() {
+        let all = profiles(&["power-saver", "balanced", "performance"]);
+        let cycle = |active| next_profile(&all, active, ProfileRequest::Cycle);
+        assert_eq!(cycle("performance"), Some("balanced"));
+        assert_eq!(cycle("balanced"), Some("power-saver"));
+        assert_eq!(cycle("power-saver"), Some("performance"));
+
+        // Without a performance driver only two profiles exist
+        let two = profiles(&["power-saver", "balanced"]);
+        assert_eq!(
+            next_profile(&two, "power-saver", ProfileRequest::Cycle),
+            Some("balanced")
+        );
+        assert_eq!(next_profile(&[], "balanced", ProfileRequest::Cycle), None);
+        let one = profiles(&["balanced"]);
+        assert_eq!(next_profile(&one, "balanced", ProfileRequest::Cycle), None);
+    }
+
+    #[test]
+    fn steps_stop_at_the_ends() {
+        let all = profiles(&["power-saver", "balanced", "performance"]);
+        let step = |active, delta| next_profile(&all, active, ProfileRequest::Step(delta));
+        assert_eq!(step("balanced", 1), Some("performance"));
+        assert_eq!(step("balanced", -1), Some("power-saver"));
+        assert_eq!(step("performance", 1), None);
+        assert_eq!(step("power-saver", -1), None);
+        assert_eq!(step("power-saver", 5), Some("performance"));
+        // An unknown active profile counts from power-saver
+        assert_eq!(step("custom", 1), Some("balanced"));
+    }
+
+    #[test]
+    fn tooltip_explains_degradation_and_holds() {
+        let mut buf = String::new();
+        format_tooltip(
+            &mut buf,
+            &ProfileState {
+                active: "balanced".to_string(),
+                degraded: String::new(),
+                holds: Vec::new(),
+            },
+        );
+        assert_eq!(buf, "Power profile: Balanced");
+
+        buf.clear();
+        format_tooltip(
+            &mut buf,
+            &ProfileState {
+                active: "performance".to_string(),
+                degraded: "lap-detected, high-operating-temperature".to_string(),
+                holds: vec![
+                    Hold {
+                        application: "com.valvesoftware.Steam".to_string(),
+                        profile: "performance".to_string(),
+                        reason: "Game running".to_string(),
+                    },
+                    Hold {
+                        application: "org.gnome.Settings".to_string(),
+                        profile: "power-saver".to_string(),
+                        reason: String::new(),
+                    },
+                ],
+            },
+        );
+        assert_eq!(
+            buf,
+            "Power profile: Performance\n\
+             Performance degraded: the computer is on a lap\n\
+             Performance degraded: high operating temperature\n\
+             Performance held by com.valvesoftware.Steam: Game running\n\
+             Power saver held by org.gnome.Settings"
+        );
+    }
+
+    #[test]
+    fn unknown_profiles_keep_their_name() {
+        assert_eq!(describe("custom"), ("custom", "\u{f0e7}"));
+        assert_eq!(describe_degradation("fan-failure"), "fan-failure");
+    }
+}
@@ -0,0 +1,343 @@
+use std::fmt::Write;
+
+use gtk::prelude::*;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::config::types::{MemoryDisplay, PressureConfig, PressureWindow};
+use crate::theme::ThemeColors;
+use crate::widgets::mini_bar::MiniBar;
+
+/// One `some`/`full` line of a /proc/pressure file, in percent
+#[derive(Debug, Clone, Copy)]
+struct Averages {
+    avg10: f64,
+    avg60: f64,
+}
+
+#[derive(Debug)]
+struct ResourcePressure {
+    name: String,
+    some: Averages,
+    /// Absent for cpu on kernels before 5.13
+    full: Option<Averages>,
+}
+
+#[derive(Debug)]
+struct PressureData {
+    resources: Vec<ResourcePressure>,
+}
+
+/// The highest stall figure across all resources for the configured window
+struct Worst<'a> {
+    resource: &'a str,
+    kind: &'static str,
+    averages: Averages,
+    value: f64,
+}
+
+impl PressureData {
+    fn worst(&self, window: PressureWindow) -> Option<Worst<'_>> {
+        let pick = |a: &Averages| match window {
+            PressureWindow::Avg10 => a.avg10,
+            PressureWindow::Avg60 => a.avg60,
+        };
+        let mut worst: Option<Worst> = None;
+        for res in &self.resources {
+            let lines =
+                std::iter::once(("some", res.some)).chain(res.full.map(|full| ("full", full)));
+            for (kind, averages) in lines {
+                let value = pick(&averages);
+                if worst.as_ref().is_none_or(|w| value > w.value) {
+                    worst = Some(Worst {
+                        resource: &res.name,
+                        kind,
+                        averages,
+                        value,
+                    });
+                }
+            }
+        }
+        worst
+    }
+}
+
+fn read_pressure(resources: &[String]) -> Option<PressureData> {
+    let resources: Vec<ResourcePressure> = resources
+        .iter()
+        .filter_map(|name| {
+            // Fails with EOPNOTSUPP when booted with psi=0
+            let contents = std::fs::read_to_string(format!("/proc/pressure/{name}")).ok()?;
+            parse_resource(name, &contents)
+        })
+        .collect();
+
+    (!resources.is_empty()).then_some(PressureData { resources })
+}
+
+/// Parse the contents of `/proc/pressure/<name>`
+fn parse_resource(name: &str, contents: &str) -> Option<ResourcePressure> {
+    let mut some = None;
+    let mut full = None;
+    for line in contents.lines() {
+        if let Some(rest) = line.strip_prefix("some ") {
+            some = parse_averages(rest);
+        } else if let Some(rest) = line.strip_prefix("full ") {
+            full = parse_averages(rest);
+        }
+    }
+    Some(ResourcePressure {
+        name: name.to_string(),
+        some: some?,
+        full,
+    })
+}
+
+/// Parse `avg10=0.00 avg60=0.00 avg300=0.00 total=0`
+fn parse_averages(line: &str) -> Option<Averages> {
+    let mut avg10 = None;
+    let mut avg60 = None;
+    for field in line.split_whitespace() {
+        if let Some(v) = field.strip_prefix("avg10=") {
+            avg10 = v.parse().ok();
+        } else if let Some(v) = field.strip_prefix("avg60=") {
+            avg60 = v.parse().ok();
+        }
+    }
+    Some(Averages {
+        avg10: avg10?,
+        avg60: avg60?,
+    })
+}
+
+/// CSS class for a stall figure: "critical", "warning" or none
+fn level_class(value: f64, warning: f64, critical: f64) -> Option<&'static str> {
+    if value >= critical {
+        Some("critical")
+    } else if value >= warning {
+        Some("warning")
+    } else {
+        None
+    }
+}
+
+fn render_format(buf: &mut String, format: &str, worst: &Worst) {
+    for part in format.split('{') {
+        let Some((key, rest)) = part.split_once('}') else {
+            buf.push_str(part);
+            continue;
+        };
+        match key {
+            "icon" => buf.push('\u{f625}'), // fa-gauge-high
+            "resource" => buf.push_str(worst.resource),
+            "kind" => buf.push_str(worst.kind),
+            "value" => {
+                let _ = write!(buf, "{:.1}", worst.value);
+            }
+            "avg10" => {
+                let _ = write!(buf, "{:.1}", worst.averages.avg10);
+            }
+            "avg60" => {
+                let _ = write!(buf, "{:.1}", worst.averages.avg60);
+            }
+            _ => {
+                buf.push_str(part);
+                continue;
+            }
+        }
+        buf.push_str(rest);
+    }
+}
+
+fn format_tooltip(buf: &mut String, data: &PressureData) {
+    buf.push_str("Pressure stall (avg10 / avg60)");
+    for res in &data.resources {
+        let _ = write!(
+            buf,
+            "\n{}: some {:.1}% / {:.1}%",
+            res.name, res.some.avg10, res.some.avg60
+        );
+        if let Some(full) = res.full {
+            let _ = write!(buf, ", full {:.1}% / {:.1}%", full.avg10, full.avg60);
+        }
+    }
+}
+
+pub fn build(config: &PressureConfig, colors: &ThemeColors) -> gtk::Widget {
+    let (tx, rx) = mpsc::channel::<Option<PressureData>>(8);
+
+    let interval_secs = config.interval;
+    let resources = config.resources.clone();
+
+    crate::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            if tx.send(read_pressure(&resources)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 2);
+    container.add_css_class("module");
+    container.add_css_class("pressure");
+    container.set_margin_start(0);
+    container.set_margin_end(0);
+
+    let label = gtk::Label::new(None);
+    label.add_css_class("module-label");
+    if config.display != MemoryDisplay::Bar {
+        container.append(&label);
+    }
+
+    // Vertical mini bar
+    let mini_bar = MiniBar::new(config.bar_width, config.bar_height, colors, true);
+    if config.display != MemoryDisplay::Text {
+        container.append(mini_bar.widget());
+    }
+
+    let format = config.format.clone();
+    let window = config.window;
+    let warning = config.warning;
+    let critical = config.critical;
+
+    let container_ref = container.clone();
+    let mut buf = String::with_capacity(32);
+    let mut tooltip_buf = String::with_capacity(128);
+    super::recv_on_main_thread(rx, move |data| {
+        container_ref.remove_css_class("warning");
+        container_ref.remove_css_class("critical");
+
+        let Some(data) = data else {
+            mini_bar.set_fraction(0.0);
+            label.set_label("");
+            super::set_tooltip_text(
+                container_ref.clone(),
+                Some("Pressure stall information unavailable"),
+            );
+            return;
+        };
+        let Some(worst) = data.worst(window) else {
+            return;
+        };
+
+        // Full bar means "at the critical threshold", since healthy systems
+        // sit in the low single digits
+        let fraction = if critical > 0.0 {
+            (worst.value / critical).min(1.0)
+        } else {
+            0.0
+        };
+        mini_bar.set_fraction(fraction);
+
+        if let Some(class) = level_class(worst.value, warning, critical) {
+            container_ref.add_css_class(class);
+        }
+
+        buf.clear();
+        render_format(&mut buf, &format, &worst);
+        label.set_label(&buf);
+
+        tooltip_buf.clear();
+        format_tooltip(&mut tooltip_buf, &data);
+        super::set_tooltip_text(container_ref.clone(), Some(&tooltip_buf));
+    });
+
+    debug!("Pressure module created");
+    container.upcast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From a 6.x kernel under a big build; cpu as older kernels report it,
+    // without a `full` line
+    const CPU: &str = "some avg10=1.52 avg60=0.87 avg300=0.42 total=18729341\n";
+    const MEMORY: &str = "some avg10=0.00 avg60=0.12 avg300=0.05 total=1234567\n\
+                          full avg10=0.00 avg60=0.08 avg300=0.03 total=987654\n";
+    const IO: &str = "some avg10=12.30 avg60=4.51 avg300=1.20 total=76543210\n\
+                      full avg10=11.02 avg60=4.02 avg300=1.00 total=70123456\n";
+
+    fn data() -> PressureData {
+        PressureData {
+            resources: [("cpu", CPU), ("memory", MEMORY), ("io", IO)]
+                .iter()
+                .map(|(name, contents)| parse_resource(name, contents).unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parses_some_and_full_lines() {
+        let cpu = parse_resource("cpu", CPU).unwrap();
+        assert_eq!((cpu.some.avg10, cpu.some.avg60), (1.52, 0.87));
+        assert!(cpu.full.is_none());
+
+        let io = parse_resource("io", IO).unwrap();
+        assert_eq!((io.some.avg10, io.some.avg60), (12.30, 4.51));
+        let full = io.full.unwrap();
+        assert_eq!((full.avg10, full.avg60), (11.02, 4.02));
+
+        assert!(parse_resource("cpu", "").is_none());
+        assert!(
+            parse_resource("cpu", "full avg10=1.00 avg60=1.00 avg300=1.00 total=1\n").is_none()
+        );
+        assert!(parse_averages("avg10=0.00 total=0").is_none());
+    }
+
+    #[test]
+    fn picks_worst_for_the_window() {
+        let data = data();
+        let worst = data.worst(PressureWindow::Avg10).unwrap();
+        assert_eq!(
+            (worst.resource, worst.kind, worst.value),
+            ("io", "some", 12.30)
+        );
+        let worst = data.worst(PressureWindow::Avg60).unwrap();
+        assert_eq!((worst.resource, worst.value), ("io", 4.51));
+
+        let idle = PressureData {
+            resources: vec![parse_resource("memory", MEMORY).unwrap()],
+        };
+        // Ties keep the first line seen
+        let worst = idle.worst(PressureWindow::Avg10).unwrap();
+        assert_eq!((worst.kind, worst.value), ("some", 0.0));
+        assert!(PressureData {
+            resources: Vec::new()
+        }
+        .worst(PressureWindow::Avg10)
+        .is_none());
+    }
+
+    #[test]
+    fn renders_format_and_level() {
+        let data = data();
+        let worst = data.worst(PressureWindow::Avg10).unwrap();
+        let mut buf = String::new();
+        render_format(
+            &mut buf,
+            "{resource} {kind} {value}% ({avg10}/{avg60})",
+            &worst,
+        );
+        assert_eq!(buf, "io some 12.3% (12.3/4.5)");
+
+        assert_eq!(level_class(worst.value, 10.0, 40.0), Some("warning"));
+        assert_eq!(level_class(40.0, 10.0, 40.0), Some("critical"));
+        assert_eq!(level_class(9.9, 10.0, 40.0), None);
+    }
+
+    #[test]
+    fn tooltip_lists_every_resource() {
+        let mut buf = String::new();
+        format_tooltip(&mut buf, &data());
+        assert_eq!(
+            buf,
+            "Pressure stall (avg10 / avg60)\n\
+             cpu: some 1.5% / 0.9%\n\
+             memory: some 0.0% / 0.1%, full 0.0% / 0.1%\n\
+             io: some 12.3% / 4.5%, full 11.0% / 4.0%"
+        );
+    }
+}
@@ -4,7 +4,7 @@ use gtk::prelude::*;
 use tokio::sync::mpsc;
 use tracing::debug;
 
-use crate::config::types::SwapConfig;
+use crate::config::types::{MemoryDisplay, SwapConfig};
 use crate::theme::ThemeColors;
 use crate::widgets::mini_bar::MiniBar;
 
@@ -12,32 +12,174 @@ use super::meminfo;
 
 #[derive(Debug)]
 struct SwapData {
-    used_bytes: u64,
-    total_bytes: u64,
-    fraction: f64,
+    usage: meminfo::Usage,
+    devices: Vec<SwapDevice>,
+    /// `(original, compressed)` bytes held in the zswap pool
+    zswap: Option<(u64, u64)>,
 }
 
+/// One line of /proc/swaps
+#[derive(Debug)]
+struct SwapDevice {
+    name: String,
+    size: u64,
+    used: u64,
+    priority: i32,
+    zram: Option<ZramStat>,
+}
+
+/// Leading fields of /sys/block/zram*/mm_stat, in bytes
+#[derive(Debug)]
+struct ZramStat {
+    orig_data_size: u64,
+    compr_data_size: u64,
+    mem_used_total: u64,
+}
+
+/// Swap usage; `{available}` is free swap and `{swap_used}` equals `{used}`
 fn read_swap() -> Option<SwapData> {
     let info = meminfo::read_meminfo()?;
 
-    if info.swap_total == 0 {
-        return Some(SwapData {
-            used_bytes: 0,
-            total_bytes: 0,
-            fraction: 0.0,
-        });
-    }
+    let used = info.swap_total.saturating_sub(info.swap_free) * 1024;
 
-    let used = info.swap_total.saturating_sub(info.swap_free);
-    let fraction = used as f64 / info.swap_total as f64;
+    let zswap = zswap_usage(info.zswapped, info.zswap, read_zswap_debugfs);
 
     Some(SwapData {
-        used_bytes: used * 1024,
-        total_bytes: info.swap_total * 1024,
-        fraction,
+        usage: meminfo::Usage {
+            used,
+            total: info.swap_total * 1024,
+            available: info.swap_free * 1024,
+            swap_used: used,
+        },
+        devices: read_swaps(),
+        zswap,
     })
 }
 
+/// zswap `(original, compressed)` bytes from the `Zswapped`/`Zswap` kB values
+/// of /proc/meminfo, else from `debugfs`; `None` while the pool is empty
+fn zswap_usage(
+    zswapped: Option<u64>,
+    zswap: Option<u64>,
+    debugfs: impl FnOnce() -> Option<(u64, u64)>,
+) -> Option<(u64, u64)> {
+    match (zswapped, zswap) {
+        (Some(original), Some(compressed)) => Some((original * 1024, compressed * 1024)),
+        _ => debugfs(),
+    }
+    .filter(|&(original, _)| original > 0)
+}
+
+fn read_swaps() -> Vec<SwapDevice> {
+    let Ok(contents) = std::fs::read_to_string("/proc/swaps") else {
+        return Vec::new();
+    };
+    let mut devices = parse_swaps(&contents);
+    for dev in &mut devices {
+        dev.zram = dev
+            .name
+            .strip_prefix("/dev/")
+            .filter(|dev| dev.starts_with("zram"))
+            .and_then(read_zram);
+    }
+    devices
+}
+
+/// Parse /proc/swaps, leaving `zram` for the caller to fill in
+fn parse_swaps(contents: &str) -> Vec<SwapDevice> {
+    // Filename Type Size Used Priority (sizes in kB); spaces in file names
+    // are escaped as \040 so whitespace splitting is safe
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let _kind = fields.next()?;
+            let size = fields.next()?.parse::<u64>().ok()? * 1024;
+            let used = fields.next()?.parse::<u64>().ok()? * 1024;
+            let priority = fields.next()?.parse().ok()?;
+            Some(SwapDevice {
+                name,
+                size,
+                used,
+                priority,
+                zram: None,
+            })
+        })
+        .collect()
+}
+
+fn read_zram(dev: &str) -> Option<ZramStat> {
+    let stat = std::fs::read_to_string(format!("/sys/block/{dev}/mm_stat")).ok()?;
+    parse_mm_stat(&stat)
+}
+
+/// Parse the leading fields of a zram device's mm_stat
+fn parse_mm_stat(stat: &str) -> Option<ZramStat> {
+    let mut fields = stat.split_whitespace().map(|f| f.parse::<u64>().ok());
+    Some(ZramStat {
+        orig_data_size: fields.next()??,
+        compr_data_size: fields.next()??,
+        mem_used_total: fields.next()??,
+    })
+}
+
+/// Kernels before 5.19 only report zswap through debugfs (root-only)
+fn read_zswap_debugfs() -> Option<(u64, u64)> {
+    let read = |file: &str| std::fs::read_to_string(format!("/sys/kernel/debug/zswap/{file}"));
+    parse_zswap_debugfs(&read("pool_total_size").ok()?, &read("stored_pages").ok()?)
+}
+
+/// `(original, compressed)` bytes from the debugfs `pool_total_size` and
+/// `stored_pages` files
+fn parse_zswap_debugfs(pool_total_size: &str, stored_pages: &str) -> Option<(u64, u64)> {
+    let pool: u64 = pool_total_size.trim().parse().ok()?;
+    let stored_pages: u64 = stored_pages.trim().parse().ok()?;
+    // Assumes 4 KiB pages, which holds on every arch with zswap in practice
+    Some((stored_pages * 4096, pool))
+}
+
+fn write_ratio(buf: &mut String, original: u64, compressed: u64) {
+    meminfo::format_bytes_into(buf, original);
+    buf.push_str(" \u{2192} ");
+    meminfo::format_bytes_into(buf, compressed);
+    if compressed > 0 {
+        let _ = write!(buf, " ({:.1}x)", original as f64 / compressed as f64);
+    }
+}
+
+fn format_tooltip(buf: &mut String, data: &SwapData) {
+    let usage = &data.usage;
+    if usage.total == 0 {
+        buf.push_str("Swap: disabled");
+    } else {
+        buf.push_str("Swap: ");
+        meminfo::format_bytes_into(buf, usage.used);
+        buf.push_str(" / ");
+        meminfo::format_bytes_into(buf, usage.total);
+        let _ = write!(buf, " ({:.0}%)", usage.fraction() * 100.0);
+    }
+
+    for dev in &data.devices {
+        let _ = write!(buf, "\n{} (priority {}): ", dev.name, dev.priority);
+        meminfo::format_bytes_into(buf, dev.used);
+        buf.push_str(" / ");
+        meminfo::format_bytes_into(buf, dev.size);
+        if let Some(ref zram) = dev.zram {
+            buf.push_str("\n  ");
+            write_ratio(buf, zram.orig_data_size, zram.compr_data_size);
+            buf.push_str(", RAM ");
+            meminfo::format_bytes_into(buf, zram.mem_used_total);
+        }
+    }
+
+    if let Some((original, compressed)) = data.zswap {
+        buf.push_str("\nzswap: ");
+        write_ratio(buf, original, compressed);
+    }
+}
+
 pub fn build(config: &SwapConfig, colors: &ThemeColors) -> gtk::Widget {
     let (tx, rx) = mpsc::channel::<SwapData>(8);
 
@@ -61,30 +203,31 @@ pub fn build(config: &SwapConfig, colors: &ThemeColors) -> gtk::Widget {
     container.set_margin_start(0);
     container.set_margin_end(0);
 
-    // Icon
-    let icon_label = gtk::Label::new(Some("\u{f0a0}")); // fa-hdd
-    icon_label.add_css_class("module-label");
-    container.append(&icon_label);
+    let label = gtk::Label::new(None);
+    label.add_css_class("module-label");
+    if config.display != MemoryDisplay::Bar {
+        container.append(&label);
+    }
 
     // Vertical mini bar
     let mini_bar = MiniBar::new(config.bar_width, config.bar_height, colors, true);
-    container.append(mini_bar.widget());
+    if config.display != MemoryDisplay::Text {
+        container.append(mini_bar.widget());
+    }
 
+    let format = config.format.clone();
     let container_ref = container.clone();
+    let mut buf = String::with_capacity(32);
     let mut tooltip_buf = String::with_capacity(64);
     super::recv_on_main_thread(rx, move |data| {
-        mini_bar.set_fraction(data.fraction);
+        mini_bar.set_fraction(data.usage.fraction());
+
+        buf.clear();
+        meminfo::render_format(&mut buf, &format, "\u{f0a0}", &data.usage); // fa-hdd
+        label.set_label(&buf);
 
         tooltip_buf.clear();
-        if data.total_bytes == 0 {
-            tooltip_buf.push_str("Swap: disabled");
-        } else {
-            tooltip_buf.push_str("Swap: ");
-            meminfo::format_bytes_into(&mut tooltip_buf, data.used_bytes);
-            tooltip_buf.push_str(" / ");
-            meminfo::format_bytes_into(&mut tooltip_buf, data.total_bytes);
-            let _ = write!(tooltip_buf, " ({:.0}%)", data.fraction * 100.0);
-        }
+        format_tooltip(&mut tooltip_buf, &data);
         super::set_tooltip_text(container_ref.clone(), Some(&tooltip_buf));
     });
 
@@ -111,3 +254,99 @@ pub fn build(config: &SwapConfig, colors: &ThemeColors) -> gtk::Widget {
     debug!("Swap module created");
     container.upcast()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A zram device ahead of a swap file whose name has a space
+    const SWAPS: &str = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/zram0                              partition\t8388604\t\t1048576\t\t100
+/swap/my\\040file                        file\t\t2097148\t\t0\t\t-2
+";
+
+    // mm_stat of a zram device holding 1 GiB compressed to 256 MiB
+    const MM_STAT: &str =
+        "1073741824 268435456 285212672        0 301989888      123      456        0        0\n";
+
+    #[test]
+    fn parses_proc_swaps() {
+        let devices = parse_swaps(SWAPS);
+        let summary: Vec<_> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.size, d.used, d.priority))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("/dev/zram0", 8388604 * 1024, 1048576 * 1024, 100),
+                ("/swap/my\\040file", 2097148 * 1024, 0, -2),
+            ]
+        );
+        assert!(devices.iter().all(|d| d.zram.is_none()));
+
+        // Header only, and a line cut short
+        assert!(parse_swaps("Filename Type Size Used Priority\n").is_empty());
+        assert!(parse_swaps("Filename\n/dev/sda2 partition 100\n").is_empty());
+    }
+
+    #[test]
+    fn parses_mm_stat() {
+        let zram = parse_mm_stat(MM_STAT).unwrap();
+        assert_eq!(
+            (
+                zram.orig_data_size,
+                zram.compr_data_size,
+                zram.mem_used_total
+            ),
+            (1 << 30, 256 << 20, 272 << 20)
+        );
+        assert!(parse_mm_stat("").is_none());
+        assert!(parse_mm_stat("4096 1024").is_none());
+        assert!(parse_mm_stat("4096 x 1024").is_none());
+    }
+
+    #[test]
+    fn zswap_from_meminfo_or_debugfs() {
+        let debugfs = || parse_zswap_debugfs("52428800\n", "51200\n");
+        assert_eq!(debugfs(), Some((200 << 20, 50 << 20)));
+        assert_eq!(parse_zswap_debugfs("", "51200"), None);
+
+        // /proc/meminfo wins when it has both lines
+        assert_eq!(
+            zswap_usage(Some(4096), Some(1024), debugfs),
+            Some((4 << 20, 1 << 20))
+        );
+        assert_eq!(zswap_usage(None, Some(1024), debugfs), debugfs());
+        assert_eq!(zswap_usage(None, None, || None), None);
+        // An empty pool isn't worth a tooltip line
+        assert_eq!(zswap_usage(Some(0), Some(0), debugfs), None);
+    }
+
+    #[test]
+    fn tooltip_lists_devices_and_compression() {
+        let mut devices = parse_swaps(SWAPS);
+        devices[0].zram = parse_mm_stat(MM_STAT);
+        let data = SwapData {
+            usage: meminfo::Usage {
+                used: 1 << 30,
+                total: 10 << 30,
+                available: 9 << 30,
+                swap_used: 1 << 30,
+            },
+            devices,
+            zswap: Some((4 << 20, 1 << 20)),
+        };
+        let mut buf = String::new();
+        format_tooltip(&mut buf, &data);
+        assert_eq!(
+            buf,
+            "Swap: 1.0 GiB / 10.0 GiB (10%)\n\
+             /dev/zram0 (priority 100): 1.0 GiB / 8.0 GiB\n  \
+             1.0 GiB \u{2192} 256 MiB (4.0x), RAM 272 MiB\n\
+             /swap/my\\040file (priority -2): 0 MiB / 2.0 GiB\n\
+             zswap: 4 MiB \u{2192} 1 MiB (4.0x)"
+        );
+    }
+}
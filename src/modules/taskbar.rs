@@ -4,7 +4,8 @@ use std::sync::OnceLock;
 use tokio::sync::mpsc;
 use tracing::{debug, error, warn};
 
-use crate::config::types::{TaskbarConfig, TaskbarDisplay};
+use crate::config::types::{Position, TaskbarConfig, TaskbarDisplay};
+use crate::widgets::popup_menu::PopupMenu;
 
 /// Info about a toplevel window
 #[derive(Debug, Clone)]
@@ -13,6 +14,12 @@ pub struct ToplevelInfo {
     pub app_id: String,
     pub title: String,
     pub focused: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// Paired with a wlr handle, so the window menu's actions work
+    pub controllable: bool,
+    pub can_fullscreen: bool,
 }
 
 /// Events from the Wayland thread to GTK
@@ -21,13 +28,22 @@ enum ToplevelEvent {
     New(ToplevelInfo),
     Update(ToplevelInfo),
     Remove(u32),
+    /// Names of the outputs windows can be made fullscreen on
+    Outputs(Vec<String>),
+    /// No toplevel protocol to watch, with the reason
+    Unavailable(String),
 }
 
 /// Requests from GTK to the Wayland thread
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum ToplevelRequest {
     Activate(u32),
     Close(u32),
+    SetMinimized(u32, bool),
+    SetMaximized(u32, bool),
+    /// Fullscreen on the named output, or wherever the compositor likes
+    SetFullscreen(u32, Option<String>),
+    UnsetFullscreen(u32),
 }
 
 /// Cache of app_id -> icon name mappings from .desktop files
@@ -37,7 +53,7 @@ fn desktop_icon_cache() -> &'static std::sync::Mutex<HashMap<String, String>> {
 }
 
 /// Resolve an app_id to an icon name, checking .desktop files
-fn resolve_icon_name(app_id: &str) -> String {
+pub(super) fn resolve_icon_name(app_id: &str) -> String {
     // Check cache first
     {
         let cache = desktop_icon_cache().lock().unwrap();
@@ -194,7 +210,95 @@ fn update_button_content(
     button.set_child(Some(&content));
 }
 
-pub fn build(config: &TaskbarConfig) -> gtk::Widget {
+/// Right-click menu with the window actions its wlr handle supports.
+/// Neither toplevel protocol can move windows between outputs or
+/// workspaces; fullscreen on a given output is the closest they get.
+fn show_window_menu(
+    menu: &PopupMenu,
+    info: &ToplevelInfo,
+    outputs: &[String],
+    request_tx: &mpsc::Sender<ToplevelRequest>,
+    max_title: usize,
+    anchor: &gtk::Button,
+) {
+    if !info.controllable {
+        return;
+    }
+    let id = info.id;
+    let mut actions = vec![
+        if info.minimized {
+            (
+                "Restore".to_string(),
+                ToplevelRequest::SetMinimized(id, false),
+            )
+        } else {
+            (
+                "Minimize".to_string(),
+                ToplevelRequest::SetMinimized(id, true),
+            )
+        },
+        if info.maximized {
+            (
+                "Unmaximize".to_string(),
+                ToplevelRequest::SetMaximized(id, false),
+            )
+        } else {
+            (
+                "Maximize".to_string(),
+                ToplevelRequest::SetMaximized(id, true),
+            )
+        },
+    ];
+    if info.can_fullscreen {
+        if info.fullscreen {
+            actions.push((
+                "Leave fullscreen".to_string(),
+                ToplevelRequest::UnsetFullscreen(id),
+            ));
+        } else {
+            actions.push((
+                "Fullscreen".to_string(),
+                ToplevelRequest::SetFullscreen(id, None),
+            ));
+            if outputs.len() > 1 {
+                for output in outputs {
+                    actions.push((
+                        format!("Fullscreen on {output}"),
+                        ToplevelRequest::SetFullscreen(id, Some(output.clone())),
+                    ));
+                }
+            }
+        }
+    }
+    actions.push(("Close".to_string(), ToplevelRequest::Close(id)));
+
+    menu.clear();
+    let header = if info.title.is_empty() {
+        &info.app_id
+    } else {
+        &info.title
+    };
+    menu.add_header(&truncate_title(header, max_title));
+    for (label, request) in actions {
+        let tx = request_tx.clone();
+        menu.add_item(&label, move || {
+            let tx = tx.clone();
+            let request = request.clone();
+            glib::spawn_future_local(async move {
+                let _ = tx.send(request).await;
+            });
+        });
+    }
+    menu.popup_at(anchor);
+}
+
+pub fn build(
+    config: &TaskbarConfig,
+    app: &gtk::Application,
+    bar_position: Position,
+    bar_height: u32,
+    bar_edge_margin: i32,
+) -> gtk::Widget {
     let (event_tx, event_rx) = mpsc::channel::<ToplevelEvent>(32);
     let (request_tx, request_rx) = mpsc::channel::<ToplevelRequest>(16);
 
@@ -206,8 +310,9 @@ pub fn build(config: &TaskbarConfig) -> gtk::Widget {
     crate::spawn(async move {
         // Run on a blocking thread since Wayland needs its own event loop
         let result = tokio::task::spawn_blocking(move || {
-            if let Err(e) = run_toplevel_watcher(event_tx, request_rx) {
+            if let Err(e) = run_toplevel_watcher(event_tx.clone(), request_rx) {
                 error!("Toplevel watcher failed: {e}");
+                let _ = event_tx.blocking_send(ToplevelEvent::Unavailable(e.to_string()));
             }
         })
         .await;
@@ -227,6 +332,18 @@ pub fn build(config: &TaskbarConfig) -> gtk::Widget {
     let buttons: std::rc::Rc<std::cell::RefCell<HashMap<u32, gtk::Button>>> =
         std::rc::Rc::new(std::cell::RefCell::new(HashMap::new()));
 
+    // Latest state per window and the outputs, for the right-click menu
+    let windows: std::rc::Rc<std::cell::RefCell<HashMap<u32, ToplevelInfo>>> =
+        std::rc::Rc::new(std::cell::RefCell::new(HashMap::new()));
+    let outputs = std::rc::Rc::new(std::cell::RefCell::new(Vec::<String>::new()));
+    let menu = std::rc::Rc::new(PopupMenu::new(
+        app,
+        "ferritebar-taskbar-menu",
+        bar_position,
+        bar_height,
+        bar_edge_margin,
+    ));
+
     // Focused-only mode: show only the active window's button
     let focused_only = std::rc::Rc::new(std::cell::Cell::new(false));
     let focused_id = std::rc::Rc::new(std::cell::Cell::new(None::<u32>));
@@ -235,6 +352,8 @@ pub fn build(config: &TaskbarConfig) -> gtk::Widget {
     let buttons_ref = buttons.clone();
     let focused_only_ref = focused_only.clone();
     let focused_id_ref = focused_id.clone();
+    let windows_ref = windows.clone();
+    let outputs_ref = outputs.clone();
 
     super::recv_on_main_thread(event_rx, move |event| match event {
         ToplevelEvent::New(info) => {
@@ -278,8 +397,32 @@ pub fn build(config: &TaskbarConfig) -> gtk::Widget {
             });
             button.add_controller(gesture);
 
+            // Right click: window menu, built from the latest state
+            let gesture = gtk::GestureClick::builder().button(3).build();
+            let windows_menu = windows_ref.clone();
+            let outputs_menu = outputs_ref.clone();
+            let menu = menu.clone();
+            let tx3 = request_tx.clone();
+            let id3 = info.id;
+            gesture.connect_released(move |gesture, _, _, _| {
+                let info = windows_menu.borrow().get(&id3).cloned();
+                let button = gesture.widget().and_downcast::<gtk::Button>();
+                if let (Some(info), Some(button)) = (info, button) {
+                    show_window_menu(
+                        &menu,
+                        &info,
+                        &outputs_menu.borrow(),
+                        &tx3,
+                        max_title,
+                        &button,
+                    );
+                }
+            });
+            button.add_controller(gesture);
+
             container_ref.append(&button);
             buttons_ref.borrow_mut().insert(info.id, button);
+            windows_ref.borrow_mut().insert(info.id, info);
             container_ref.set_visible(true);
         }
         ToplevelEvent::Update(info) => {
@@ -311,15 +454,35 @@ pub fn build(config: &TaskbarConfig) -> gtk::Widget {
                     btn.set_visible(Some(*id) == cur);
                 }
             }
+            if let Some(window) = windows_ref.borrow_mut().get_mut(&info.id) {
+                *window = info;
+            }
         }
         ToplevelEvent::Remove(id) => {
             if let Some(button) = buttons_ref.borrow_mut().remove(&id) {
                 container_ref.remove(&button);
             }
+            windows_ref.borrow_mut().remove(&id);
             if buttons_ref.borrow().is_empty() {
                 container_ref.set_visible(false);
             }
         }
+        ToplevelEvent::Outputs(list) => {
+            *outputs_ref.borrow_mut() = list;
+        }
+        ToplevelEvent::Unavailable(reason) => {
+            buttons_ref.borrow_mut().clear();
+            windows_ref.borrow_mut().clear();
+            while let Some(child) = container_ref.first_child() {
+                container_ref.remove(&child);
+            }
+            let label = gtk::Label::new(Some("Tasks"));
+            label.add_css_class("module-label");
+            label.add_css_class("taskbar-unavailable");
+            container_ref.append(&label);
+            super::set_tooltip_text(container_ref.clone(), Some(&format!("Taskbar: {reason}")));
+            container_ref.set_visible(true);
+        }
     });
 
     // IPC: toggle focused-only mode when `ferritebar msg taskbar-focus` is called
@@ -364,58 +527,245 @@ fn truncate_title(title: &str, max_len: usize) -> std::borrow::Cow<'_, str> {
 // ---- Wayland toplevel watcher (runs on blocking thread) ----
 
 use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_output::{self, WlOutput};
 use wayland_client::protocol::wl_registry;
 use wayland_client::protocol::wl_seat::WlSeat;
-use wayland_client::{delegate_noop, Connection, Dispatch, QueueHandle};
+use wayland_client::{delegate_noop, Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1::{self, ExtForeignToplevelHandleV1},
+    ext_foreign_toplevel_list_v1::{self, ExtForeignToplevelListV1},
+};
 use wayland_protocols_wlr::foreign_toplevel::v1::client::{
     zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
     zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
 };
 
-/// Internal state for the Wayland event loop
+/// Internal state for the Wayland event loop.
+///
+/// Windows are the wlr handles when the compositor has
+/// `zwlr_foreign_toplevel_manager_v1`, so every button can activate and
+/// close its window. An `ext_foreign_toplevel_list_v1` entry showing the
+/// same app_id and title, when no other window does, is attached to the
+/// handle for its identifier. Without a wlr manager the ext entries are
+/// listed directly.
 struct WaylandState {
     event_tx: mpsc::Sender<ToplevelEvent>,
     request_rx: mpsc::Receiver<ToplevelRequest>,
-    handles: Vec<HandleState>,
+    /// Windows come from the ext list rather than from wlr handles
+    ext_listed: bool,
+    toplevels: Vec<Toplevel>,
+    ext_windows: Vec<ExtWindow>,
     seat: Option<WlSeat>,
+    outputs: Vec<Output>,
     next_id: u32,
 }
 
-struct HandleState {
+struct Output {
+    output: WlOutput,
+    /// Registry name, to notice the output going away
+    global: u32,
+    /// Connector name; wl_output v4 and later
+    name: Option<String>,
+}
+
+/// A window the taskbar shows
+struct Toplevel {
     id: u32,
-    handle: ZwlrForeignToplevelHandleV1,
-    pending_title: String,
-    pending_app_id: String,
-    pending_focused: bool,
-    initial_done: bool,
+    /// Handle for state and actions; `None` when listed from ext
+    wlr: Option<ZwlrForeignToplevelHandleV1>,
+    title: String,
+    app_id: String,
+    state: WindowState,
+    /// Sent to GTK yet
+    shown: bool,
 }
 
-/// Poll a file descriptor for readability with a timeout.
-///
-/// Uses a bare `extern "C"` declaration to avoid adding `nix`/`libc` as
-/// dependencies. Returns `true` if the fd is readable before the timeout.
-fn poll_readable(raw_fd: i32, timeout_ms: i32) -> bool {
-    use std::os::raw::{c_int, c_short};
+/// An entry of the ext list
+struct ExtWindow {
+    handle: ExtForeignToplevelHandleV1,
+    identifier: String,
+    title: String,
+    app_id: String,
+    /// The toplevel this entry names
+    toplevel: Option<u32>,
+}
+
+/// The `state` array of a wlr toplevel handle
+#[derive(Debug, Clone, Copy, Default)]
+struct WindowState {
+    maximized: bool,
+    minimized: bool,
+    activated: bool,
+    fullscreen: bool,
+}
+
+impl WindowState {
+    fn parse(raw: &[u8]) -> Self {
+        let mut state = Self::default();
+        // Parse u32 array from raw bytes
+        for bytes in raw.chunks_exact(4) {
+            match u32::from_le_bytes(bytes.try_into().unwrap()) {
+                0 => state.maximized = true,
+                1 => state.minimized = true,
+                2 => state.activated = true,
+                3 => state.fullscreen = true,
+                _ => {}
+            }
+        }
+        state
+    }
+}
 
-    #[repr(C)]
-    struct PollFd {
-        fd: c_int,
-        events: c_short,
-        revents: c_short,
+/// Index pairs of `entries` and `windows` showing the same (app_id, title),
+/// for the values exactly one of each shows
+fn unique_matches(entries: &[(&str, &str)], windows: &[(&str, &str)]) -> Vec<(usize, usize)> {
+    let count =
+        |list: &[(&str, &str)], key: &(&str, &str)| list.iter().filter(|k| *k == key).count();
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, key)| count(entries, key) == 1 && count(windows, key) == 1)
+        .filter_map(|(e, key)| Some((e, windows.iter().position(|k| k == key)?)))
+        .collect()
+}
+
+impl WaylandState {
+    fn add_toplevel(&mut self, wlr: Option<ZwlrForeignToplevelHandleV1>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.toplevels.push(Toplevel {
+            id,
+            wlr,
+            title: String::new(),
+            app_id: String::new(),
+            state: WindowState::default(),
+            shown: false,
+        });
+        id
     }
 
-    extern "C" {
-        // nfds_t is `unsigned long` on Linux
-        fn poll(fds: *mut PollFd, nfds: u64, timeout: c_int) -> c_int;
+    fn wlr_handle_for(&self, id: u32) -> Option<&ZwlrForeignToplevelHandleV1> {
+        self.toplevels
+            .iter()
+            .find(|t| t.id == id)
+            .and_then(|t| t.wlr.as_ref())
     }
 
-    const POLLIN: c_short = 0x001;
-    let mut pfd = PollFd {
-        fd: raw_fd,
-        events: POLLIN,
-        revents: 0,
-    };
-    unsafe { poll(&mut pfd, 1, timeout_ms) > 0 }
+    /// Attach ext entries to the wlr-listed windows showing the same app_id
+    /// and title. A pair whose names have diverged is dropped first, and
+    /// identical windows (two terminals in the same directory) get no entry
+    /// until something tells them apart. Either way the window keeps its
+    /// wlr handle and stays controllable.
+    fn pair(&mut self) {
+        for ext in &mut self.ext_windows {
+            let Some(id) = ext.toplevel else {
+                continue;
+            };
+            let stale = self
+                .toplevels
+                .iter()
+                .any(|t| t.id == id && (t.app_id != ext.app_id || t.title != ext.title));
+            if stale {
+                ext.toplevel = None;
+            }
+        }
+
+        let entries: Vec<usize> = (0..self.ext_windows.len())
+            .filter(|idx| self.ext_windows[*idx].toplevel.is_none())
+            .collect();
+        let windows: Vec<&Toplevel> = self
+            .toplevels
+            .iter()
+            .filter(|t| !self.ext_windows.iter().any(|e| e.toplevel == Some(t.id)))
+            .collect();
+
+        let entry_keys: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|idx| {
+                let ext = &self.ext_windows[*idx];
+                (ext.app_id.as_str(), ext.title.as_str())
+            })
+            .collect();
+        let window_keys: Vec<(&str, &str)> = windows
+            .iter()
+            .map(|t| (t.app_id.as_str(), t.title.as_str()))
+            .collect();
+        let matches: Vec<(usize, u32)> = unique_matches(&entry_keys, &window_keys)
+            .into_iter()
+            .map(|(e, w)| (entries[e], windows[w].id))
+            .collect();
+
+        for (idx, id) in matches {
+            let ext = &mut self.ext_windows[idx];
+            ext.toplevel = Some(id);
+            debug!(
+                "Taskbar: window {id} ({}) is ext toplevel {}",
+                ext.app_id, ext.identifier
+            );
+        }
+    }
+
+    /// Tell GTK about a toplevel's current state
+    fn emit(&mut self, id: u32) {
+        let Some(toplevel) = self.toplevels.iter_mut().find(|t| t.id == id) else {
+            return;
+        };
+        let wlr = toplevel.wlr.as_ref();
+        let info = ToplevelInfo {
+            id,
+            app_id: toplevel.app_id.clone(),
+            title: toplevel.title.clone(),
+            focused: toplevel.state.activated,
+            minimized: toplevel.state.minimized,
+            maximized: toplevel.state.maximized,
+            fullscreen: toplevel.state.fullscreen,
+            controllable: wlr.is_some(),
+            // set_fullscreen arrived in version 2
+            can_fullscreen: wlr.is_some_and(|h| h.version() >= 2),
+        };
+        if toplevel.shown {
+            let _ = self.event_tx.blocking_send(ToplevelEvent::Update(info));
+        } else if !info.app_id.is_empty() {
+            // Skip empty app_id (XWayland dialogs)
+            toplevel.shown = true;
+            let _ = self.event_tx.blocking_send(ToplevelEvent::New(info));
+        }
+    }
+
+    fn remove(&mut self, id: u32) {
+        let Some(idx) = self.toplevels.iter().position(|t| t.id == id) else {
+            return;
+        };
+        let toplevel = self.toplevels.remove(idx);
+        for ext in &mut self.ext_windows {
+            if ext.toplevel == Some(id) {
+                ext.toplevel = None;
+            }
+        }
+        if toplevel.shown {
+            let _ = self.event_tx.blocking_send(ToplevelEvent::Remove(id));
+        }
+    }
+
+    fn add_output(
+        &mut self,
+        registry: &wl_registry::WlRegistry,
+        global: u32,
+        version: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        let output = registry.bind::<WlOutput, _, _>(global, version.min(4), qh, ());
+        self.outputs.push(Output {
+            output,
+            global,
+            name: None,
+        });
+    }
+
+    fn send_outputs(&self) {
+        let names = self.outputs.iter().filter_map(|o| o.name.clone()).collect();
+        let _ = self.event_tx.blocking_send(ToplevelEvent::Outputs(names));
+    }
 }
 
 fn run_toplevel_watcher(
@@ -428,19 +778,48 @@ fn run_toplevel_watcher(
     let (globals, mut queue) = registry_queue_init::<WaylandState>(&conn)?;
     let qh = queue.handle();
 
-    // Bind to toplevel manager
-    let _manager: ZwlrForeignToplevelManagerV1 = globals.bind(&qh, 1..=3, ())?;
+    let ext_list: Option<ExtForeignToplevelListV1> = globals.bind(&qh, 1..=1, ()).ok();
+    let wlr_manager: Option<ZwlrForeignToplevelManagerV1> = globals.bind(&qh, 1..=3, ()).ok();
+    match (&ext_list, &wlr_manager) {
+        (None, None) => {
+            return Err(
+                "the compositor offers neither ext-foreign-toplevel-list-v1 \
+                        nor wlr-foreign-toplevel-management"
+                    .into(),
+            );
+        }
+        (Some(_), None) => warn!(
+            "Taskbar: no wlr-foreign-toplevel-management; windows are listed but \
+             can't be activated or closed, and focus isn't shown"
+        ),
+        (Some(_), Some(_)) => debug!(
+            "Taskbar: using wlr-foreign-toplevel-management, with \
+             ext-foreign-toplevel-list-v1 identifiers"
+        ),
+        (None, Some(_)) => debug!("Taskbar: using wlr-foreign-toplevel-management"),
+    }
 
     // Bind to seat (needed for activate)
-    let seat: WlSeat = globals.bind(&qh, 1..=9, ())?;
+    let seat: Option<WlSeat> = wlr_manager
+        .as_ref()
+        .and_then(|_| globals.bind(&qh, 1..=9, ()).ok());
 
     let mut state = WaylandState {
         event_tx,
         request_rx,
-        handles: Vec::new(),
-        seat: Some(seat),
+        ext_listed: wlr_manager.is_none(),
+        toplevels: Vec::new(),
+        ext_windows: Vec::new(),
+        seat,
+        outputs: Vec::new(),
         next_id: 1,
     };
+    // Outputs name the "Fullscreen on" entries of the window menu
+    for global in globals.contents().clone_list() {
+        if global.interface == WlOutput::interface().name {
+            state.add_output(globals.registry(), global.name, global.version, &qh);
+        }
+    }
 
     // Initial roundtrip to get existing toplevels
     queue.roundtrip(&mut state)?;
@@ -463,21 +842,53 @@ fn run_toplevel_watcher(
         while let Ok(request) = state.request_rx.try_recv() {
             match request {
                 ToplevelRequest::Activate(id) => {
-                    if let Some(hs) = state.handles.iter().find(|h| h.id == id) {
-                        if let Some(ref seat) = state.seat {
-                            hs.handle.activate(seat);
-                        }
+                    if let (Some(handle), Some(seat)) = (state.wlr_handle_for(id), &state.seat) {
+                        handle.activate(seat);
                     }
                 }
                 ToplevelRequest::Close(id) => {
-                    if let Some(hs) = state.handles.iter().find(|h| h.id == id) {
-                        hs.handle.close();
+                    if let Some(handle) = state.wlr_handle_for(id) {
+                        handle.close();
+                    }
+                }
+                ToplevelRequest::SetMinimized(id, minimized) => {
+                    if let Some(handle) = state.wlr_handle_for(id) {
+                        if minimized {
+                            handle.set_minimized();
+                        } else {
+                            handle.unset_minimized();
+                        }
+                    }
+                }
+                ToplevelRequest::SetMaximized(id, maximized) => {
+                    if let Some(handle) = state.wlr_handle_for(id) {
+                        if maximized {
+                            handle.set_maximized();
+                        } else {
+                            handle.unset_maximized();
+                        }
+                    }
+                }
+                ToplevelRequest::SetFullscreen(id, output) => {
+                    let output = output.and_then(|name| {
+                        state
+                            .outputs
+                            .iter()
+                            .find(|o| o.name.as_deref() == Some(name.as_str()))
+                    });
+                    if let Some(handle) = state.wlr_handle_for(id) {
+                        handle.set_fullscreen(output.map(|o| &o.output));
+                    }
+                }
+                ToplevelRequest::UnsetFullscreen(id) => {
+                    if let Some(handle) = state.wlr_handle_for(id) {
+                        handle.unset_fullscreen();
                     }
                 }
             }
         }
 
-        // Flush any outgoing requests (activate, close) to the compositor.
+        // Flush any outgoing requests (activate, close, ...) to the compositor.
         conn.flush()?;
 
         // Dispatch events that are already buffered in the queue.
@@ -488,7 +899,7 @@ fn run_toplevel_watcher(
         // quiet.  If `prepare_read` returns None there are already-queued
         // events ready; `dispatch_pending` above will handle them next iteration.
         if let Some(guard) = queue.prepare_read() {
-            if poll_readable(raw_fd, 50) {
+            if crate::sys::poll_readable(raw_fd, 50) {
                 guard.read()?;
             }
             // Timeout — guard dropped without reading, loop back to try_recv.
@@ -503,22 +914,152 @@ fn run_toplevel_watcher(
 
 impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandState {
     fn event(
-        _state: &mut Self,
-        _proxy: &wl_registry::WlRegistry,
-        _event: wl_registry::Event,
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
         _data: &GlobalListContents,
         _conn: &Connection,
-        _qh: &QueueHandle<Self>,
+        qh: &QueueHandle<Self>,
     ) {
-        // Registry events handled by GlobalListContents
+        // Follow hotplugged outputs; everything else was bound at startup
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == WlOutput::interface().name => {
+                state.add_output(registry, name, version, qh);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(idx) = state.outputs.iter().position(|o| o.global == name) {
+                    let output = state.outputs.remove(idx);
+                    if output.output.version() >= 3 {
+                        output.output.release();
+                    }
+                    state.send_outputs();
+                }
+            }
+            _ => {}
+        }
     }
 }
 
 delegate_noop!(WaylandState: ignore WlSeat);
 
+impl Dispatch<WlOutput, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_output::Event::Name { name } => {
+                if let Some(o) = state.outputs.iter_mut().find(|o| o.output == *output) {
+                    o.name = Some(name);
+                }
+            }
+            wl_output::Event::Done => state.send_outputs(),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtForeignToplevelListV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ExtForeignToplevelListV1,
+        event: ext_foreign_toplevel_list_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } => {
+                state.ext_windows.push(ExtWindow {
+                    handle: toplevel,
+                    identifier: String::new(),
+                    title: String::new(),
+                    app_id: String::new(),
+                    toplevel: None,
+                });
+            }
+            ext_foreign_toplevel_list_v1::Event::Finished => {
+                warn!("Toplevel list finished");
+            }
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(WaylandState, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE =>
+            (ExtForeignToplevelHandleV1, ())
+    ]);
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        handle: &ExtForeignToplevelHandleV1,
+        event: ext_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let Some(idx) = state.ext_windows.iter().position(|e| e.handle == *handle) else {
+            return;
+        };
+
+        match event {
+            ext_foreign_toplevel_handle_v1::Event::Identifier { identifier } => {
+                state.ext_windows[idx].identifier = identifier;
+            }
+            ext_foreign_toplevel_handle_v1::Event::Title { title } => {
+                state.ext_windows[idx].title = title;
+            }
+            ext_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                state.ext_windows[idx].app_id = app_id;
+            }
+            ext_foreign_toplevel_handle_v1::Event::Done => {
+                if !state.ext_listed {
+                    state.pair();
+                    return;
+                }
+                let id = match state.ext_windows[idx].toplevel {
+                    Some(id) => id,
+                    None => {
+                        let id = state.add_toplevel(None);
+                        state.ext_windows[idx].toplevel = Some(id);
+                        id
+                    }
+                };
+                let ext = &state.ext_windows[idx];
+                if let Some(toplevel) = state.toplevels.iter_mut().find(|t| t.id == id) {
+                    toplevel.title = ext.title.clone();
+                    toplevel.app_id = ext.app_id.clone();
+                }
+                state.emit(id);
+            }
+            ext_foreign_toplevel_handle_v1::Event::Closed => {
+                let ext = state.ext_windows.remove(idx);
+                if !state.ext_listed {
+                    // A twin left behind may now be told apart
+                    state.pair();
+                } else if let Some(id) = ext.toplevel {
+                    state.remove(id);
+                }
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
 impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _proxy: &ZwlrForeignToplevelManagerV1,
         event: zwlr_foreign_toplevel_manager_v1::Event,
         _data: &(),
@@ -526,9 +1067,8 @@ impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandState {
         _qh: &QueueHandle<Self>,
     ) {
         match event {
-            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel: _ } => {
-                // New toplevel handle - events will arrive via handle dispatch
-                debug!("New toplevel handle advertised");
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state.add_toplevel(Some(toplevel));
             }
             zwlr_foreign_toplevel_manager_v1::Event::Finished => {
                 warn!("Toplevel manager finished");
@@ -552,91 +1092,56 @@ impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for WaylandState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        const STATE_ACTIVATED: u32 = 2;
-
-        // Find or create handle state
-        let hs_idx = state.handles.iter().position(|h| h.handle == *handle);
+        let Some(toplevel) = state
+            .toplevels
+            .iter_mut()
+            .find(|t| t.wlr.as_ref() == Some(handle))
+        else {
+            return;
+        };
+        let id = toplevel.id;
 
         match event {
-            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => {
-                if let Some(idx) = hs_idx {
-                    state.handles[idx].pending_title = title;
-                } else {
-                    // New handle, create state
-                    let id = state.next_id;
-                    state.next_id += 1;
-                    state.handles.push(HandleState {
-                        id,
-                        handle: handle.clone(),
-                        pending_title: title,
-                        pending_app_id: String::new(),
-                        pending_focused: false,
-                        initial_done: false,
-                    });
-                }
-            }
-            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
-                if let Some(idx) = hs_idx {
-                    state.handles[idx].pending_app_id = app_id;
-                } else {
-                    let id = state.next_id;
-                    state.next_id += 1;
-                    state.handles.push(HandleState {
-                        id,
-                        handle: handle.clone(),
-                        pending_title: String::new(),
-                        pending_app_id: app_id,
-                        pending_focused: false,
-                        initial_done: false,
-                    });
-                }
-            }
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => toplevel.title = title,
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => toplevel.app_id = app_id,
             zwlr_foreign_toplevel_handle_v1::Event::State { state: wl_state } => {
-                let focused = if wl_state.len() >= 4 {
-                    // Parse u32 array from raw bytes
-                    (0..wl_state.len() / 4).any(|i| {
-                        let bytes: [u8; 4] =
-                            wl_state[i * 4..i * 4 + 4].try_into().unwrap_or([0; 4]);
-                        u32::from_le_bytes(bytes) == STATE_ACTIVATED
-                    })
-                } else {
-                    false
-                };
-
-                if let Some(idx) = state.handles.iter().position(|h| h.handle == *handle) {
-                    state.handles[idx].pending_focused = focused;
-                }
+                toplevel.state = WindowState::parse(&wl_state);
             }
             zwlr_foreign_toplevel_handle_v1::Event::Done => {
-                if let Some(idx) = state.handles.iter().position(|h| h.handle == *handle) {
-                    let hs = &mut state.handles[idx];
-                    let info = ToplevelInfo {
-                        id: hs.id,
-                        app_id: hs.pending_app_id.clone(),
-                        title: hs.pending_title.clone(),
-                        focused: hs.pending_focused,
-                    };
-
-                    if hs.initial_done {
-                        // Update existing
-                        let _ = state.event_tx.blocking_send(ToplevelEvent::Update(info));
-                    } else {
-                        // Skip empty app_id (XWayland dialogs)
-                        if !info.app_id.is_empty() {
-                            hs.initial_done = true;
-                            let _ = state.event_tx.blocking_send(ToplevelEvent::New(info));
-                        }
-                    }
-                }
+                state.emit(id);
+                state.pair();
             }
             zwlr_foreign_toplevel_handle_v1::Event::Closed => {
-                if let Some(idx) = state.handles.iter().position(|h| h.handle == *handle) {
-                    let id = state.handles[idx].id;
-                    state.handles.remove(idx);
-                    let _ = state.event_tx.blocking_send(ToplevelEvent::Remove(id));
-                }
+                state.remove(id);
+                state.pair();
+                handle.destroy();
             }
             _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_only_unique_windows() {
+        let entries = [("foot", "~"), ("firefox", "News"), ("foot", "~")];
+        let windows = [("firefox", "News"), ("foot", "~"), ("foot", "~")];
+        assert_eq!(unique_matches(&entries, &windows), [(1, 0)]);
+
+        // Once one terminal changes directory, both tell apart
+        let entries = [("foot", "~/src"), ("firefox", "News"), ("foot", "~")];
+        let windows = [("foot", "~"), ("foot", "~/src")];
+        assert_eq!(unique_matches(&entries, &windows), [(0, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn waits_for_the_other_side() {
+        // A window whose twin hasn't been announced yet could be either
+        assert!(unique_matches(&[("foot", "~")], &[("foot", "~"), ("foot", "~")]).is_empty());
+        assert!(unique_matches(&[("foot", "~"), ("foot", "~")], &[("foot", "~")]).is_empty());
+        assert!(unique_matches(&[("foot", "~")], &[]).is_empty());
+    }
+}
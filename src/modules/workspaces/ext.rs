@@ -0,0 +1,509 @@
+//! ext-workspace-v1 workspaces over the bar's own Wayland connection
+
+use tokio::sync::mpsc;
+use tracing::warn;
+use wayland_client::globals::{registry_queue_init, GlobalList, GlobalListContents};
+use wayland_client::protocol::wl_output::{self, WlOutput};
+use wayland_client::protocol::wl_registry;
+use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+use wayland_protocols::ext::workspace::v1::client::{
+    ext_workspace_group_handle_v1::{self, ExtWorkspaceGroupHandleV1},
+    ext_workspace_handle_v1::{self, ExtWorkspaceHandleV1},
+    ext_workspace_manager_v1::{self, ExtWorkspaceManagerV1},
+};
+
+use super::{GroupInfo, WorkspaceBackend, WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+
+struct WorkspaceState {
+    internal_id: u64,
+    handle: ExtWorkspaceHandleV1,
+    name: String,
+    id: Option<String>,
+    coords: Vec<u32>,
+    state: u32,
+    capabilities: u32,
+    group_id: Option<u32>,
+    removed: bool,
+    serial: u64,
+}
+
+struct WorkspaceGroupState {
+    id: u32,
+    handle: ExtWorkspaceGroupHandleV1,
+    outputs: Vec<WlOutput>,
+    removed: bool,
+    capabilities: u32,
+}
+
+/// A bound `wl_output`; groups report outputs as these objects
+struct OutputState {
+    global_name: u32,
+    handle: WlOutput,
+    /// Connector name, sent from wl_output v4 on
+    name: Option<String>,
+}
+
+/// Internal state for the Wayland event loop
+struct WaylandState {
+    event_tx: mpsc::Sender<WorkspaceEvent>,
+    request_rx: mpsc::Receiver<WorkspaceRequest>,
+    manager: Option<ExtWorkspaceManagerV1>,
+    workspaces: Vec<WorkspaceState>,
+    groups: Vec<WorkspaceGroupState>,
+    outputs: Vec<OutputState>,
+    next_workspace_id: u64,
+    next_group_id: u32,
+    next_serial: u64,
+    finished: bool,
+}
+
+const STATE_ACTIVE: u32 = 1;
+const STATE_URGENT: u32 = 2;
+const STATE_HIDDEN: u32 = 4;
+
+const CAP_REMOVE: u32 = 4;
+const CAP_ASSIGN: u32 = 8;
+const GROUP_CAP_CREATE: u32 = 1;
+
+/// A Wayland connection with `ext_workspace_manager_v1` bound
+pub(super) struct ExtBackend {
+    conn: Connection,
+    globals: GlobalList,
+    queue: EventQueue<WaylandState>,
+    manager: ExtWorkspaceManagerV1,
+}
+
+impl ExtBackend {
+    /// Fails when the compositor doesn't offer ext-workspace-v1
+    pub(super) fn connect() -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::connect_to_env()?;
+        let (globals, queue) = registry_queue_init::<WaylandState>(&conn)?;
+        let manager = globals.bind(&queue.handle(), 1..=1, ())?;
+        Ok(Self {
+            conn,
+            globals,
+            queue,
+            manager,
+        })
+    }
+}
+
+impl WorkspaceBackend for ExtBackend {
+    fn name(&self) -> &'static str {
+        "ext-workspace-v1"
+    }
+
+    fn run(
+        self: Box<Self>,
+        event_tx: mpsc::Sender<WorkspaceEvent>,
+        request_rx: mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        run(*self, event_tx, request_rx)
+    }
+}
+
+fn run(
+    backend: ExtBackend,
+    event_tx: mpsc::Sender<WorkspaceEvent>,
+    request_rx: mpsc::Receiver<WorkspaceRequest>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::io::{AsFd, AsRawFd};
+
+    let ExtBackend {
+        conn,
+        globals,
+        mut queue,
+        manager,
+    } = backend;
+    let qh = queue.handle();
+
+    let mut state = WaylandState {
+        event_tx,
+        request_rx,
+        manager: Some(manager),
+        workspaces: Vec::new(),
+        groups: Vec::new(),
+        outputs: Vec::new(),
+        next_workspace_id: 1,
+        next_group_id: 1,
+        next_serial: 1,
+        finished: false,
+    };
+
+    // Groups only report outputs this client has bound
+    globals.contents().with_list(|list| {
+        for global in list {
+            if global.interface == WlOutput::interface().name {
+                state.bind_output(globals.registry(), global.name, global.version, &qh);
+            }
+        }
+    });
+
+    // Initial roundtrip to get existing workspaces
+    queue.roundtrip(&mut state)?;
+
+    let raw_fd = conn.as_fd().as_raw_fd();
+
+    loop {
+        let mut needs_commit = false;
+
+        while let Ok(request) = state.request_rx.try_recv() {
+            match request {
+                WorkspaceRequest::Activate(id) => {
+                    if let Some(ws) = state.workspaces.iter().find(|w| w.internal_id == id) {
+                        if (ws.capabilities & 1) != 0 {
+                            ws.handle.activate();
+                            needs_commit = true;
+                        }
+                    }
+                }
+                WorkspaceRequest::Create { group, name } => {
+                    if let Some(g) = state.groups.iter().find(|g| g.id == group) {
+                        if (g.capabilities & GROUP_CAP_CREATE) != 0 {
+                            g.handle.create_workspace(name);
+                            needs_commit = true;
+                        }
+                    }
+                }
+                WorkspaceRequest::Remove(id) => {
+                    if let Some(ws) = state.workspaces.iter().find(|w| w.internal_id == id) {
+                        if (ws.capabilities & CAP_REMOVE) != 0 {
+                            ws.handle.remove();
+                            needs_commit = true;
+                        }
+                    }
+                }
+                WorkspaceRequest::Assign { id, group } => {
+                    let ws = state.workspaces.iter().find(|w| w.internal_id == id);
+                    let g = state.groups.iter().find(|g| g.id == group);
+                    if let (Some(ws), Some(g)) = (ws, g) {
+                        if (ws.capabilities & CAP_ASSIGN) != 0 {
+                            ws.handle.assign(&g.handle);
+                            needs_commit = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        if needs_commit {
+            if let Some(ref manager) = state.manager {
+                manager.commit();
+            }
+        }
+
+        conn.flush()?;
+        queue.dispatch_pending(&mut state)?;
+
+        if state.finished {
+            break;
+        }
+
+        if let Some(guard) = queue.prepare_read() {
+            if crate::sys::poll_readable(raw_fd, 50) {
+                guard.read()?;
+            }
+        }
+
+        queue.dispatch_pending(&mut state)?;
+    }
+
+    Ok(())
+}
+
+fn parse_u32_array(data: &[u8]) -> Vec<u32> {
+    let mut out = Vec::new();
+    let mut idx = 0;
+    while idx + 4 <= data.len() {
+        let bytes: [u8; 4] = data[idx..idx + 4].try_into().unwrap_or([0; 4]);
+        out.push(u32::from_ne_bytes(bytes));
+        idx += 4;
+    }
+    out
+}
+
+impl WaylandState {
+    fn bind_output(
+        &mut self,
+        registry: &wl_registry::WlRegistry,
+        global_name: u32,
+        version: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        let handle = registry.bind::<WlOutput, _, _>(global_name, version.min(4), qh, ());
+        self.outputs.push(OutputState {
+            global_name,
+            handle,
+            name: None,
+        });
+    }
+
+    /// Connector names for a group's outputs, skipping ones not named yet
+    fn output_names(&self, group: &WorkspaceGroupState) -> Vec<String> {
+        group
+            .outputs
+            .iter()
+            .filter_map(|handle| {
+                self.outputs
+                    .iter()
+                    .find(|o| o.handle == *handle)
+                    .and_then(|o| o.name.clone())
+            })
+            .collect()
+    }
+}
+
+fn emit_snapshot(state: &mut WaylandState) {
+    let mut grouped: std::collections::BTreeMap<u32, Vec<&WorkspaceState>> =
+        std::collections::BTreeMap::new();
+
+    for ws in &state.workspaces {
+        if ws.removed {
+            continue;
+        }
+        let group_id = ws.group_id.unwrap_or(0);
+        grouped.entry(group_id).or_default().push(ws);
+    }
+
+    let mut snapshot = Vec::new();
+
+    for (group_id, mut list) in grouped {
+        let outputs = state
+            .groups
+            .iter()
+            .find(|g| g.id == group_id && !g.removed)
+            .map(|g| state.output_names(g))
+            .unwrap_or_default();
+
+        list.sort_by(|a, b| {
+            if !a.coords.is_empty() || !b.coords.is_empty() {
+                match a.coords.cmp(&b.coords) {
+                    std::cmp::Ordering::Equal => a.serial.cmp(&b.serial),
+                    other => other,
+                }
+            } else {
+                a.serial.cmp(&b.serial)
+            }
+        });
+
+        for (idx, ws) in list.iter().enumerate() {
+            snapshot.push(WorkspaceInfo {
+                id: ws.internal_id,
+                name: ws.name.clone(),
+                index: (idx + 1) as u32,
+                group: group_id,
+                active: (ws.state & STATE_ACTIVE) != 0,
+                focused: false,
+                urgent: (ws.state & STATE_URGENT) != 0,
+                hidden: (ws.state & STATE_HIDDEN) != 0,
+                outputs: outputs.clone(),
+                can_remove: (ws.capabilities & CAP_REMOVE) != 0,
+                can_assign: (ws.capabilities & CAP_ASSIGN) != 0,
+                windows: Vec::new(),
+            });
+        }
+    }
+
+    state.workspaces.retain(|w| !w.removed);
+    state.groups.retain(|g| !g.removed);
+
+    let groups = state
+        .groups
+        .iter()
+        .map(|g| GroupInfo {
+            id: g.id,
+            outputs: state.output_names(g),
+            can_create: (g.capabilities & GROUP_CAP_CREATE) != 0,
+        })
+        .collect();
+    let _ = state.event_tx.blocking_send(WorkspaceEvent::Groups(groups));
+
+    let _ = state
+        .event_tx
+        .blocking_send(WorkspaceEvent::Snapshot(snapshot));
+}
+
+// ---- Wayland dispatch implementations ----
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandState {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        // Other registry events are handled by GlobalListContents; outputs
+        // are tracked here so hotplugged monitors can be matched
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == WlOutput::interface().name => {
+                state.bind_output(registry, name, version, qh);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(idx) = state.outputs.iter().position(|o| o.global_name == name) {
+                    let output = state.outputs.remove(idx);
+                    if output.handle.version() >= 3 {
+                        output.handle.release();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlOutput, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            if let Some(o) = state.outputs.iter_mut().find(|o| o.handle == *output) {
+                o.name = Some(name);
+            }
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceManagerV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ExtWorkspaceManagerV1,
+        event: ext_workspace_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_workspace_manager_v1::Event::WorkspaceGroup { workspace_group } => {
+                let id = state.next_group_id;
+                state.next_group_id += 1;
+                state.groups.push(WorkspaceGroupState {
+                    id,
+                    handle: workspace_group,
+                    outputs: Vec::new(),
+                    removed: false,
+                    capabilities: 0,
+                });
+            }
+            ext_workspace_manager_v1::Event::Workspace { workspace } => {
+                let id = state.next_workspace_id;
+                state.next_workspace_id += 1;
+                let serial = state.next_serial;
+                state.next_serial += 1;
+                state.workspaces.push(WorkspaceState {
+                    internal_id: id,
+                    handle: workspace,
+                    name: String::new(),
+                    id: None,
+                    coords: Vec::new(),
+                    state: 0,
+                    capabilities: 0,
+                    group_id: None,
+                    removed: false,
+                    serial,
+                });
+            }
+            ext_workspace_manager_v1::Event::Done => {
+                emit_snapshot(state);
+            }
+            ext_workspace_manager_v1::Event::Finished => {
+                warn!("Workspace manager finished");
+                state.finished = true;
+            }
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(WaylandState, ExtWorkspaceManagerV1, [
+        ext_workspace_manager_v1::EVT_WORKSPACE_GROUP_OPCODE => (ExtWorkspaceGroupHandleV1, ()),
+        ext_workspace_manager_v1::EVT_WORKSPACE_OPCODE => (ExtWorkspaceHandleV1, ())
+    ]);
+}
+
+impl Dispatch<ExtWorkspaceGroupHandleV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        group: &ExtWorkspaceGroupHandleV1,
+        event: ext_workspace_group_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let Some(g_idx) = state.groups.iter().position(|g| g.handle == *group) else {
+            return;
+        };
+
+        match event {
+            ext_workspace_group_handle_v1::Event::Capabilities { capabilities } => {
+                state.groups[g_idx].capabilities = capabilities.into();
+            }
+            ext_workspace_group_handle_v1::Event::OutputEnter { output } => {
+                state.groups[g_idx].outputs.push(output);
+            }
+            ext_workspace_group_handle_v1::Event::OutputLeave { output } => {
+                state.groups[g_idx].outputs.retain(|o| o != &output);
+            }
+            ext_workspace_group_handle_v1::Event::WorkspaceEnter { workspace } => {
+                if let Some(ws) = state.workspaces.iter_mut().find(|w| w.handle == workspace) {
+                    ws.group_id = Some(state.groups[g_idx].id);
+                }
+            }
+            ext_workspace_group_handle_v1::Event::WorkspaceLeave { workspace } => {
+                if let Some(ws) = state.workspaces.iter_mut().find(|w| w.handle == workspace) {
+                    ws.group_id = None;
+                }
+            }
+            ext_workspace_group_handle_v1::Event::Removed => {
+                state.groups[g_idx].removed = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceHandleV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        workspace: &ExtWorkspaceHandleV1,
+        event: ext_workspace_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let Some(ws_idx) = state.workspaces.iter().position(|w| w.handle == *workspace) else {
+            return;
+        };
+
+        match event {
+            ext_workspace_handle_v1::Event::Id { id } => {
+                state.workspaces[ws_idx].id = Some(id);
+            }
+            ext_workspace_handle_v1::Event::Name { name } => {
+                state.workspaces[ws_idx].name = name;
+            }
+            ext_workspace_handle_v1::Event::Coordinates { coordinates } => {
+                state.workspaces[ws_idx].coords = parse_u32_array(&coordinates);
+            }
+            ext_workspace_handle_v1::Event::State { state: ws_state } => {
+                state.workspaces[ws_idx].state = ws_state.into();
+            }
+            ext_workspace_handle_v1::Event::Capabilities { capabilities } => {
+                state.workspaces[ws_idx].capabilities = capabilities.into();
+            }
+            ext_workspace_handle_v1::Event::Removed => {
+                state.workspaces[ws_idx].removed = true;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Commands run when workspaces change (`hooks` in the workspaces config).
+//!
+//! Runs are debounced: a hook waits `debounce_ms` and only runs for the last
+//! change in that time, so scrolling through ten workspaces forks once.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::config::types::WorkspacesConfig;
+
+use super::{expand_placeholders, WorkspaceInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Hook {
+    ActiveChange,
+    Urgent,
+    Created,
+    Removed,
+}
+
+pub(super) struct Hooks {
+    on_active_change: Option<String>,
+    on_urgent: Option<String>,
+    on_created: Option<String>,
+    on_removed: Option<String>,
+    debounce: Duration,
+    /// Waiting runs; active changes share one slot, the rest have one per
+    /// workspace so creating several at once runs for each
+    pending: RefCell<HashMap<(Hook, Option<u64>), glib::SourceId>>,
+}
+
+impl Hooks {
+    pub(super) fn new(config: &WorkspacesConfig) -> Rc<Self> {
+        let hooks = &config.hooks;
+        let legacy_sync = config
+            .sync_command
+            .clone()
+            .filter(|_| config.sync_only_active);
+        Rc::new(Self {
+            on_active_change: hooks.on_active_change.clone().or(legacy_sync),
+            on_urgent: hooks.on_urgent.clone(),
+            on_created: hooks.on_created.clone(),
+            on_removed: hooks.on_removed.clone(),
+            debounce: Duration::from_millis(hooks.debounce_ms),
+            pending: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn command(&self, hook: Hook) -> Option<&str> {
+        match hook {
+            Hook::ActiveChange => self.on_active_change.as_deref(),
+            Hook::Urgent => self.on_urgent.as_deref(),
+            Hook::Created => self.on_created.as_deref(),
+            Hook::Removed => self.on_removed.as_deref(),
+        }
+    }
+
+    /// Schedule the hooks for what changed between two snapshots of the
+    /// workspaces this bar runs hooks for
+    pub(super) fn changed(
+        self: &Rc<Self>,
+        before: &[WorkspaceInfo],
+        after: &[WorkspaceInfo],
+        initial: bool,
+    ) {
+        for (hook, info) in changes(before, after, initial) {
+            self.schedule(hook, info);
+        }
+    }
+
+    fn schedule(self: &Rc<Self>, hook: Hook, info: &WorkspaceInfo) {
+        let Some(template) = self.command(hook) else {
+            return;
+        };
+        let key = (hook, (hook != Hook::ActiveChange).then_some(info.id));
+        if let Some(source) = self.pending.borrow_mut().remove(&key) {
+            source.remove();
+        }
+
+        let command = expand_placeholders(template, None, info);
+        let info = info.clone();
+        let hooks = Rc::downgrade(self);
+        let source = glib::timeout_add_local_once(self.debounce, move || {
+            if let Some(hooks) = hooks.upgrade() {
+                hooks.pending.borrow_mut().remove(&key);
+            }
+            run(command, &info);
+        });
+        self.pending.borrow_mut().insert(key, source);
+    }
+}
+
+/// The hooks due between two snapshots, with the workspace each is for.
+/// `initial` is the first snapshot, where everything is new: only the active
+/// workspace's hook runs then.
+fn changes<'a>(
+    before: &'a [WorkspaceInfo],
+    after: &'a [WorkspaceInfo],
+    initial: bool,
+) -> Vec<(Hook, &'a WorkspaceInfo)> {
+    let find = |list: &'a [WorkspaceInfo], id: u64| list.iter().find(|w| w.id == id);
+
+    let mut out = Vec::new();
+    for info in after {
+        let old = find(before, info.id);
+        if info.active && !old.is_some_and(|w| w.active) {
+            out.push((Hook::ActiveChange, info));
+        }
+        if info.urgent && !old.is_some_and(|w| w.urgent) {
+            out.push((Hook::Urgent, info));
+        }
+        if old.is_none() && !initial {
+            out.push((Hook::Created, info));
+        }
+    }
+    for info in before {
+        if find(after, info.id).is_none() {
+            out.push((Hook::Removed, info));
+        }
+    }
+    out
+}
+
+fn run(command: String, info: &WorkspaceInfo) {
+    let name = if info.name.is_empty() {
+        info.index.to_string()
+    } else {
+        info.name.clone()
+    };
+    let index = info.index.to_string();
+    let group = info.group.to_string();
+    crate::spawn(async move {
+        let _ = tokio::process::Command::new("sh")
+            .arg("-lc")
+            .arg(&command)
+            .env("FERRITEBAR_WS_NAME", name)
+            .env("FERRITEBAR_WS_INDEX", index)
+            .env("FERRITEBAR_WS_GROUP", group)
+            .spawn();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mock::MockBackend;
+    use super::super::{test_workspace, WorkspaceRequest};
+    use super::*;
+
+    fn due(before: &[WorkspaceInfo], after: &[WorkspaceInfo], initial: bool) -> Vec<(Hook, u64)> {
+        changes(before, after, initial)
+            .into_iter()
+            .map(|(hook, info)| (hook, info.id))
+            .collect()
+    }
+
+    fn list(active: u64) -> Vec<WorkspaceInfo> {
+        (1..=3)
+            .map(|id| WorkspaceInfo {
+                active: id == active,
+                ..test_workspace(id, &id.to_string(), 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn initial_snapshot_only_runs_active_hook() {
+        assert_eq!(due(&[], &list(2), true), [(Hook::ActiveChange, 2)]);
+        // A later first appearance is a creation
+        assert_eq!(
+            due(&[], &list(2), false),
+            [
+                (Hook::Created, 1),
+                (Hook::ActiveChange, 2),
+                (Hook::Created, 2),
+                (Hook::Created, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn active_and_urgent_fire_when_they_turn_on() {
+        assert_eq!(due(&list(1), &list(1), false), []);
+        assert_eq!(due(&list(1), &list(3), false), [(Hook::ActiveChange, 3)]);
+
+        let mut urgent = list(1);
+        urgent[1].urgent = true;
+        assert_eq!(due(&list(1), &urgent, false), [(Hook::Urgent, 2)]);
+        assert_eq!(due(&urgent, &urgent, false), []);
+    }
+
+    #[test]
+    fn created_and_removed() {
+        let before = list(1);
+        let mut after = list(1);
+        after.remove(1);
+        after.push(test_workspace(4, "web", 1));
+        assert_eq!(
+            due(&before, &after, false),
+            [(Hook::Created, 4), (Hook::Removed, 2)]
+        );
+        assert_eq!(due(&before, &[], false).len(), 3);
+    }
+
+    #[test]
+    fn follows_mock_snapshots() {
+        let mut backend = MockBackend::new();
+        let mut before = backend.snapshot();
+        assert_eq!(
+            due(&[], &before, true),
+            [(Hook::ActiveChange, 1), (Hook::ActiveChange, 4)]
+        );
+
+        let cases = [
+            (WorkspaceRequest::Activate(2), vec![(Hook::ActiveChange, 2)]),
+            (
+                WorkspaceRequest::Create {
+                    group: 2,
+                    name: "web".to_string(),
+                },
+                vec![(Hook::Created, 5)],
+            ),
+            // The group's first workspace takes over as active
+            (
+                WorkspaceRequest::Remove(2),
+                vec![(Hook::ActiveChange, 1), (Hook::Removed, 2)],
+            ),
+            (
+                WorkspaceRequest::Assign { id: 4, group: 1 },
+                vec![(Hook::ActiveChange, 5)],
+            ),
+        ];
+        for (request, hooks) in cases {
+            let label = format!("{request:?}");
+            backend.apply(request);
+            let after = backend.snapshot();
+            assert_eq!(due(&before, &after, false), hooks, "{label}");
+            before = after;
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Hyprland workspaces: queries over `.socket.sock`, events from
+//! `.socket2.sock`
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use super::ipc::{self, SnapshotSender};
+use super::{WorkspaceBackend, WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+
+#[derive(Deserialize)]
+struct HyprWorkspace {
+    /// Negative for named workspaces (from -1337 down) and special
+    /// (scratchpad) ones (-99..=-2)
+    id: i64,
+    /// "special" or "special:<name>" for special workspaces
+    name: String,
+    monitor: String,
+}
+
+#[derive(Deserialize, Default)]
+struct WorkspaceRef {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyprMonitor {
+    focused: bool,
+    active_workspace: WorkspaceRef,
+    /// id 0 when no special workspace is open
+    #[serde(default)]
+    special_workspace: WorkspaceRef,
+}
+
+#[derive(Deserialize)]
+struct HyprClient {
+    address: String,
+    class: String,
+    workspace: WorkspaceRef,
+}
+
+/// Events that can change the workspace list, which windows they hold or
+/// which ones are shown. Hyprland sends each of these alongside a "v2" twin.
+const REFRESH_EVENTS: &[&str] = &[
+    "workspace",
+    "focusedmon",
+    "activespecial",
+    "createworkspace",
+    "destroyworkspace",
+    "moveworkspace",
+    "renameworkspace",
+    "monitoradded",
+    "monitorremoved",
+    "openwindow",
+    "closewindow",
+    "movewindow",
+];
+
+/// Directory holding the instance's sockets; `$XDG_RUNTIME_DIR/hypr` since
+/// Hyprland 0.40, `/tmp/hypr` before
+pub(super) fn socket_dir() -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    if signature.is_empty() {
+        return None;
+    }
+    if let Ok(runtime) = std::env::var("XDG_RUNTIME_DIR") {
+        let dir = Path::new(&runtime).join("hypr").join(&signature);
+        if dir.exists() {
+            return Some(dir);
+        }
+    }
+    Some(Path::new("/tmp/hypr").join(signature))
+}
+
+/// The socket Hyprland broadcasts events on
+pub(super) fn event_socket(dir: &Path) -> PathBuf {
+    dir.join(".socket2.sock")
+}
+
+/// One request per connection; Hyprland closes the socket after replying
+fn request(dir: &Path, cmd: &str) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(dir.join(".socket.sock"))?;
+    stream.write_all(cmd.as_bytes())?;
+    stream.flush()?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
+
+fn query<T: serde::de::DeserializeOwned>(
+    dir: &Path,
+    what: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_str(&request(dir, &format!("j/{what}"))?)?)
+}
+
+fn dispatch(dir: &Path, args: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let reply = request(dir, &format!("dispatch {args}"))?;
+    if reply.trim() != "ok" {
+        return Err(reply.trim().to_string().into());
+    }
+    Ok(())
+}
+
+fn query_workspaces(
+    dir: &Path,
+    urgent: &HashSet<String>,
+) -> Result<Vec<(WorkspaceInfo, String)>, Box<dyn std::error::Error>> {
+    Ok(build_list(
+        query(dir, "workspaces")?,
+        &query::<Vec<HyprMonitor>>(dir, "monitors")?,
+        query(dir, "clients")?,
+        urgent,
+    ))
+}
+
+fn build_list(
+    mut workspaces: Vec<HyprWorkspace>,
+    monitors: &[HyprMonitor],
+    clients: Vec<HyprClient>,
+    urgent: &HashSet<String>,
+) -> Vec<(WorkspaceInfo, String)> {
+    let mut windows: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut urgent_workspaces = HashSet::new();
+    for client in clients {
+        if urgent.contains(&client.address) {
+            urgent_workspaces.insert(client.workspace.id);
+        }
+        windows
+            .entry(client.workspace.id)
+            .or_default()
+            .push(client.class);
+    }
+
+    // Numbered workspaces first on each monitor, then named ones in creation
+    // order, then special ones
+    workspaces.sort_by_key(|ws| (is_special(&ws.name), ws.id < 0, ws.id.unsigned_abs()));
+    let mut last_index: HashMap<String, u32> = HashMap::new();
+    let mut out = Vec::with_capacity(workspaces.len());
+    for ws in workspaces {
+        let last = last_index.entry(ws.monitor.clone()).or_insert(0);
+        // Numbered workspaces keep their number; the rest count on from the
+        // highest one so indices stay unique on the monitor
+        *last = if ws.id > 0 { ws.id as u32 } else { *last + 1 };
+        let special = is_special(&ws.name);
+        let info = WorkspaceInfo {
+            id: ws.id as u64,
+            index: *last,
+            name: ws.name,
+            group: 0,
+            active: monitors
+                .iter()
+                .any(|m| m.active_workspace.id == ws.id || m.special_workspace.id == ws.id),
+            focused: monitors
+                .iter()
+                .any(|m| m.focused && m.active_workspace.id == ws.id),
+            urgent: urgent_workspaces.contains(&ws.id),
+            hidden: special,
+            outputs: Vec::new(),
+            can_remove: false,
+            can_assign: !special,
+            windows: windows.remove(&ws.id).unwrap_or_default(),
+        };
+        out.push((info, ws.monitor));
+    }
+    out
+}
+
+/// Track urgent windows from one socket2 line; returns whether the list
+/// needs querying again
+fn apply_event(urgent: &mut HashSet<String>, line: &str) -> bool {
+    let (event, data) = line.split_once(">>").unwrap_or((line, ""));
+    match event {
+        "urgent" => {
+            urgent.insert(format!("0x{data}"));
+            true
+        }
+        "activewindowv2" | "closewindow" => {
+            urgent.remove(&format!("0x{data}")) || event == "closewindow"
+        }
+        _ => REFRESH_EVENTS.contains(&event),
+    }
+}
+
+fn is_special(name: &str) -> bool {
+    name == "special" || name.starts_with("special:")
+}
+
+/// How dispatchers name a workspace: named ones have no stable number
+fn selector(id: u64, name: &str) -> String {
+    if (id as i64) > 0 {
+        id.to_string()
+    } else {
+        format!("name:{name}")
+    }
+}
+
+fn handle_request(
+    dir: &Path,
+    sender: &SnapshotSender,
+    names: &HashMap<u64, String>,
+    request: WorkspaceRequest,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match request {
+        WorkspaceRequest::Activate(id) => {
+            let Some(name) = names.get(&id) else {
+                return Ok(());
+            };
+            if is_special(name) {
+                let special = name.strip_prefix("special:").unwrap_or("");
+                dispatch(dir, &format!("togglespecialworkspace {special}"))?;
+            } else {
+                dispatch(dir, &format!("workspace {}", selector(id, name)))?;
+            }
+        }
+        WorkspaceRequest::Assign { id, group } => {
+            let (Some(name), Some(monitor)) = (names.get(&id), sender.output(group)) else {
+                return Ok(());
+            };
+            dispatch(
+                dir,
+                &format!("moveworkspacetomonitor {} {monitor}", selector(id, name)),
+            )?;
+        }
+        // Never offered: creating a Hyprland workspace also switches to it
+        WorkspaceRequest::Create { .. } | WorkspaceRequest::Remove(_) => {}
+    }
+    Ok(())
+}
+
+pub(super) struct HyprlandBackend {
+    pub(super) dir: PathBuf,
+}
+
+impl WorkspaceBackend for HyprlandBackend {
+    fn name(&self) -> &'static str {
+        "Hyprland IPC"
+    }
+
+    /// Watch Hyprland's workspaces until the receiver is dropped, reconnecting if
+    /// the event socket goes away
+    fn run(
+        self: Box<Self>,
+        event_tx: mpsc::Sender<WorkspaceEvent>,
+        request_rx: mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        ipc::run_reconnecting(self.name(), event_tx, request_rx, |sender, request_rx| {
+            watch(&self.dir, sender, request_rx)
+        })
+    }
+}
+
+fn watch(
+    dir: &Path,
+    sender: &mut SnapshotSender,
+    request_rx: &mut mpsc::Receiver<WorkspaceRequest>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut events = BufReader::new(UnixStream::connect(event_socket(dir))?);
+    debug!("Workspaces: using Hyprland IPC at {}", dir.display());
+
+    // Addresses ("0x...") of windows that asked for attention and haven't
+    // been focused since
+    let mut urgent: HashSet<String> = HashSet::new();
+    let mut names: HashMap<u64, String> = HashMap::new();
+    let mut refresh = true;
+    let mut line = String::new();
+    loop {
+        if refresh {
+            let list = query_workspaces(dir, &urgent)?;
+            names = list
+                .iter()
+                .map(|(info, _)| (info.id, info.name.clone()))
+                .collect();
+            if !sender.send(list) {
+                return Ok(());
+            }
+            refresh = false;
+        }
+
+        loop {
+            match request_rx.try_recv() {
+                Ok(request) => {
+                    if let Err(e) = handle_request(dir, sender, &names, request) {
+                        warn!("Hyprland workspace request failed: {e}");
+                    }
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        while ipc::wait_readable(&events, ipc::POLL_MS) {
+            line.clear();
+            if events.read_line(&mut line)? == 0 {
+                return Err("event socket closed".into());
+            }
+            refresh |= apply_event(&mut urgent, line.trim_end());
+            if events.buffer().is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use super::super::ipc::script::{self, Watcher};
+    use super::*;
+
+    // Trimmed `j/workspaces`, `j/monitors` and `j/clients` replies from
+    // Hyprland 0.45: numbered, named and special workspaces on two monitors
+    const WORKSPACES: &str = r#"[
+        {"id": 3, "name": "3", "monitor": "DP-1", "windows": 1},
+        {"id": 1, "name": "1", "monitor": "DP-1", "windows": 1},
+        {"id": -1338, "name": "chat", "monitor": "DP-1", "windows": 0},
+        {"id": -98, "name": "special:term", "monitor": "DP-1", "windows": 1},
+        {"id": -1337, "name": "mail", "monitor": "DP-1", "windows": 1},
+        {"id": 2, "name": "2", "monitor": "HDMI-A-1", "windows": 0}
+    ]"#;
+
+    const MONITORS: &str = r#"[
+        {"id": 0, "name": "DP-1", "focused": true,
+         "activeWorkspace": {"id": 1, "name": "1"},
+         "specialWorkspace": {"id": -98, "name": "special:term"}},
+        {"id": 1, "name": "HDMI-A-1", "focused": false,
+         "activeWorkspace": {"id": 2, "name": "2"},
+         "specialWorkspace": {"id": 0, "name": ""}}
+    ]"#;
+
+    const CLIENTS: &str = r#"[
+        {"address": "0x55d1a0", "class": "firefox", "workspace": {"id": 1, "name": "1"}},
+        {"address": "0x55d1b0", "class": "thunderbird",
+         "workspace": {"id": -1337, "name": "mail"}},
+        {"address": "0x55d1c0", "class": "kitty",
+         "workspace": {"id": -98, "name": "special:term"}},
+        {"address": "0x55d1d0", "class": "foot", "workspace": {"id": 3, "name": "3"}}
+    ]"#;
+
+    fn list(urgent: &HashSet<String>) -> Vec<(WorkspaceInfo, String)> {
+        build_list(
+            serde_json::from_str(WORKSPACES).unwrap(),
+            &serde_json::from_str::<Vec<HyprMonitor>>(MONITORS).unwrap(),
+            serde_json::from_str(CLIENTS).unwrap(),
+            urgent,
+        )
+    }
+
+    #[test]
+    fn orders_numbered_named_then_special() {
+        let list = list(&HashSet::new());
+        let summary: Vec<_> = list
+            .iter()
+            .map(|(w, output)| (w.name.as_str(), w.index, w.hidden, output.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("1", 1, false, "DP-1"),
+                ("2", 2, false, "HDMI-A-1"),
+                ("3", 3, false, "DP-1"),
+                ("mail", 4, false, "DP-1"),
+                ("chat", 5, false, "DP-1"),
+                ("special:term", 6, true, "DP-1"),
+            ]
+        );
+        let assignable: Vec<_> = list.iter().map(|(w, _)| w.can_assign).collect();
+        assert_eq!(assignable, [true, true, true, true, true, false]);
+    }
+
+    #[test]
+    fn marks_shown_focused_and_windows() {
+        let list = list(&HashSet::new());
+        let find = |name: &str| &list.iter().find(|(w, _)| w.name == name).unwrap().0;
+
+        assert!(find("1").active && find("1").focused);
+        assert!(find("2").active && !find("2").focused);
+        // An open special workspace counts as shown, but never as focused
+        assert!(find("special:term").active && !find("special:term").focused);
+        assert!(!find("mail").active);
+        assert_eq!(find("mail").windows, ["thunderbird"]);
+        assert_eq!(find("special:term").windows, ["kitty"]);
+        assert!(find("chat").windows.is_empty());
+    }
+
+    #[test]
+    fn tracks_urgent_windows_from_events() {
+        let mut urgent = HashSet::new();
+        assert!(apply_event(&mut urgent, "urgent>>55d1b0"));
+        assert!(list(&urgent)
+            .iter()
+            .all(|(w, _)| w.urgent == (w.name == "mail")));
+
+        // Focusing another window changes nothing; focusing the urgent one
+        // clears it
+        assert!(!apply_event(&mut urgent, "activewindowv2>>55d1a0"));
+        assert!(apply_event(&mut urgent, "activewindowv2>>55d1b0"));
+        assert!(urgent.is_empty());
+
+        assert!(apply_event(&mut urgent, "closewindow>>55d1a0"));
+        assert!(apply_event(&mut urgent, "workspace>>3"));
+        assert!(apply_event(&mut urgent, "createworkspace>>chat"));
+        assert!(!apply_event(&mut urgent, "activewindow>>foot,~"));
+        assert!(!apply_event(&mut urgent, "windowtitlev2>>55d1d0,~"));
+    }
+
+    #[test]
+    fn names_workspaces_for_dispatchers() {
+        assert!(is_special("special"));
+        assert!(is_special("special:term"));
+        assert!(!is_special("specialist"));
+        assert_eq!(selector(3, "3"), "3");
+        assert_eq!(selector(-1337i64 as u64, "mail"), "name:mail");
+    }
+
+    #[test]
+    fn watches_and_dispatches_over_the_sockets() {
+        let dir = script::socket_dir("hyprland");
+        let requests = UnixListener::bind(dir.join(".socket.sock")).unwrap();
+        let events = UnixListener::bind(dir.join(".socket2.sock")).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut events, _) = events.accept().unwrap();
+            let mut seen = Vec::new();
+            // One request per connection, answered by closing it
+            let mut serve = |reply: &str| {
+                let (mut stream, _) = requests.accept().unwrap();
+                stream.set_read_timeout(Some(script::TIMEOUT)).unwrap();
+                let mut buf = [0u8; 256];
+                let len = stream.read(&mut buf).unwrap();
+                seen.push(String::from_utf8(buf[..len].to_vec()).unwrap());
+                stream.write_all(reply.as_bytes()).unwrap();
+            };
+
+            for reply in [WORKSPACES, MONITORS, CLIENTS] {
+                serve(reply);
+            }
+            events.write_all(b"urgent>>55d1b0\n").unwrap();
+            for reply in [WORKSPACES, MONITORS, CLIENTS, "ok", "ok", "ok"] {
+                serve(reply);
+            }
+            (seen, events)
+        });
+
+        let mut watcher = Watcher::start(watch, dir.clone());
+        let list = watcher.snapshot();
+        let names: Vec<_> = list.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["1", "3", "mail", "chat", "special:term", "2"]);
+        assert!(list.iter().all(|w| !w.urgent));
+        let list = watcher.snapshot();
+        assert!(list.iter().all(|w| w.urgent == (w.name == "mail")));
+
+        watcher.send(WorkspaceRequest::Activate(-1337i64 as u64));
+        watcher.send(WorkspaceRequest::Activate(-98i64 as u64));
+        // Groups are numbered in the order outputs first appear: DP-1, HDMI-A-1
+        watcher.send(WorkspaceRequest::Assign { id: 3, group: 2 });
+        let (seen, _events) = server.join().unwrap();
+        let query = ["j/workspaces", "j/monitors", "j/clients"];
+        let mut expected = [query, query].concat();
+        expected.extend([
+            "dispatch workspace name:mail",
+            "dispatch togglespecialworkspace term",
+            "dispatch moveworkspacetomonitor 3 HDMI-A-1",
+        ]);
+        assert_eq!(seen, expected);
+
+        assert_eq!(watcher.stop(), Ok(()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,305 @@
+//! Plumbing shared by the compositor IPC backends (sway, Hyprland, niri),
+//! which all group workspaces by output name.
+
+use std::io::{BufReader, Read};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use super::{GroupInfo, WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+
+/// Delay before the first reconnect after the compositor socket goes away
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between reconnects while the socket stays dead
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// How long an event wait blocks before pending requests are handled
+pub(super) const POLL_MS: i32 = 50;
+
+/// Sends `Groups` + `Snapshot` pairs, with one group per output and group ids
+/// that stay stable while the watcher runs
+pub(super) struct SnapshotSender {
+    event_tx: mpsc::Sender<WorkspaceEvent>,
+    /// Output names; group id is position + 1
+    outputs: Vec<String>,
+    last: Option<(Vec<GroupInfo>, Vec<WorkspaceInfo>)>,
+    /// A list was read from the compositor since the last `take_connected`
+    connected: bool,
+}
+
+impl SnapshotSender {
+    pub(super) fn new(event_tx: mpsc::Sender<WorkspaceEvent>) -> Self {
+        Self {
+            event_tx,
+            outputs: Vec::new(),
+            last: None,
+            connected: false,
+        }
+    }
+
+    /// Group id for an output; an empty name is a group without an output
+    pub(super) fn group(&mut self, output: &str) -> u32 {
+        let pos = match self.outputs.iter().position(|o| o == output) {
+            Some(pos) => pos,
+            None => {
+                self.outputs.push(output.to_string());
+                self.outputs.len() - 1
+            }
+        };
+        pos as u32 + 1
+    }
+
+    pub(super) fn output(&self, group: u32) -> Option<&str> {
+        let pos = (group as usize).checked_sub(1)?;
+        self.outputs.get(pos).map(String::as_str)
+    }
+
+    /// Fill in each workspace's group and outputs from `output_of` and send
+    /// the result, unless it matches the last one sent.
+    ///
+    /// Returns false once the receiver is dropped.
+    pub(super) fn send(&mut self, mut list: Vec<(WorkspaceInfo, String)>) -> bool {
+        self.connected = true;
+        let mut groups: Vec<GroupInfo> = Vec::new();
+        for (info, output) in &mut list {
+            info.group = self.group(output);
+            info.outputs = if output.is_empty() {
+                Vec::new()
+            } else {
+                vec![output.clone()]
+            };
+            if !groups.iter().any(|g| g.id == info.group) {
+                groups.push(GroupInfo {
+                    id: info.group,
+                    outputs: info.outputs.clone(),
+                    can_create: false,
+                });
+            }
+        }
+        groups.sort_by_key(|g| g.id);
+
+        let mut snapshot: Vec<WorkspaceInfo> = list.into_iter().map(|(info, _)| info).collect();
+        snapshot.sort_by_key(|w| (w.group, w.index));
+
+        let current = (groups, snapshot);
+        if self.last.as_ref() == Some(&current) {
+            return true;
+        }
+        let (groups, snapshot) = current.clone();
+        self.last = Some(current);
+
+        self.event_tx
+            .blocking_send(WorkspaceEvent::Groups(groups))
+            .is_ok()
+            && self
+                .event_tx
+                .blocking_send(WorkspaceEvent::Snapshot(snapshot))
+                .is_ok()
+    }
+
+    /// Whether a list was sent since the last call
+    fn take_connected(&mut self) -> bool {
+        std::mem::take(&mut self.connected)
+    }
+}
+
+/// Whether something accepts connections on `path`. Compositor variables
+/// outlive their compositor in nested or restarted sessions, so a set
+/// variable alone doesn't mean the IPC is there.
+pub(super) fn probe(path: &Path) -> bool {
+    match UnixStream::connect(path) {
+        Ok(_) => true,
+        Err(e) => {
+            debug!("Workspaces: no IPC at {}: {e}", path.display());
+            false
+        }
+    }
+}
+
+/// Reconnect delays for one backend: doubling while the compositor stays
+/// away, back to the start once a connection delivers a list
+struct Backoff {
+    delay: Duration,
+    /// Within an outage whose first failure was already logged
+    failing: bool,
+}
+
+impl Backoff {
+    fn new() -> Self {
+        Self {
+            delay: RECONNECT_DELAY,
+            failing: false,
+        }
+    }
+
+    /// Record a failed watch; `connected` is whether it got as far as a list.
+    /// Returns how long to wait and whether this failure starts an outage.
+    fn failed(&mut self, connected: bool) -> (Duration, bool) {
+        if connected {
+            *self = Self::new();
+        }
+        let first = !self.failing;
+        self.failing = true;
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_RECONNECT_DELAY);
+        (delay, first)
+    }
+}
+
+/// Run `watch` until the receiver is dropped, reconnecting with backoff when
+/// it fails. Only the first failure of each outage is logged as a warning.
+pub(super) fn run_reconnecting(
+    name: &str,
+    event_tx: mpsc::Sender<WorkspaceEvent>,
+    mut request_rx: mpsc::Receiver<WorkspaceRequest>,
+    mut watch: impl FnMut(
+        &mut SnapshotSender,
+        &mut mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sender = SnapshotSender::new(event_tx);
+    let mut backoff = Backoff::new();
+    loop {
+        let e = match watch(&mut sender, &mut request_rx) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        let (delay, first) = backoff.failed(sender.take_connected());
+        if first {
+            warn!("{name} workspace backend: {e}; reconnecting");
+        } else {
+            debug!("{name} workspace backend: {e}; retrying in {delay:?}");
+        }
+        std::thread::sleep(delay);
+        // Drop clicks made while disconnected
+        loop {
+            match request_rx.try_recv() {
+                Ok(_) => {}
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Wait up to `timeout_ms` for `reader` to have data, counting what it has
+/// already buffered
+pub(super) fn wait_readable<R: Read + AsRawFd>(reader: &BufReader<R>, timeout_ms: i32) -> bool {
+    !reader.buffer().is_empty()
+        || crate::sys::poll_readable(reader.get_ref().as_raw_fd(), timeout_ms)
+}
+
+/// Runs a backend's `watch` against a compositor scripted by the test
+#[cfg(test)]
+pub(super) mod script {
+    use std::path::{Path, PathBuf};
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use super::super::{WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+    use super::SnapshotSender;
+
+    /// How long the scripted side waits on the backend before failing
+    pub(in super::super) const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// An empty directory for a test's sockets
+    pub(in super::super) fn socket_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ferritebar-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    type Watch = fn(
+        &Path,
+        &mut SnapshotSender,
+        &mut mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// A backend's `watch` on its own thread
+    pub(in super::super) struct Watcher {
+        events: mpsc::Receiver<WorkspaceEvent>,
+        requests: mpsc::Sender<WorkspaceRequest>,
+        thread: JoinHandle<Result<(), String>>,
+    }
+
+    impl Watcher {
+        pub(in super::super) fn start(watch: Watch, path: PathBuf) -> Self {
+            let (event_tx, events) = mpsc::channel(8);
+            let (requests, mut request_rx) = mpsc::channel(8);
+            let thread = std::thread::spawn(move || {
+                let mut sender = SnapshotSender::new(event_tx);
+                watch(&path, &mut sender, &mut request_rx).map_err(|e| e.to_string())
+            });
+            Self {
+                events,
+                requests,
+                thread,
+            }
+        }
+
+        /// The next snapshot, skipping the groups sent before it
+        pub(in super::super) fn snapshot(&mut self) -> Vec<WorkspaceInfo> {
+            loop {
+                match self.events.blocking_recv() {
+                    Some(WorkspaceEvent::Snapshot(list)) => return list,
+                    Some(_) => {}
+                    None => panic!("watcher stopped before sending a snapshot"),
+                }
+            }
+        }
+
+        pub(in super::super) fn send(&self, request: WorkspaceRequest) {
+            self.requests.blocking_send(request).unwrap();
+        }
+
+        /// Drop the request channel, which ends a healthy watcher, and return
+        /// how it ended
+        pub(in super::super) fn stop(self) -> Result<(), String> {
+            drop(self.requests);
+            self.thread.join().expect("watcher panicked")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_logs_once_per_outage() {
+        let mut backoff = Backoff::new();
+        let delays: Vec<_> = (0..9).map(|_| backoff.failed(false)).collect();
+        assert_eq!(delays[0], (Duration::from_millis(500), true));
+        assert_eq!(delays[1], (Duration::from_secs(1), false));
+        assert_eq!(delays[2], (Duration::from_secs(2), false));
+        assert_eq!(delays[8], (MAX_RECONNECT_DELAY, false));
+
+        // A connection that got a list ends the outage
+        assert_eq!(backoff.failed(true), (RECONNECT_DELAY, true));
+    }
+
+    #[test]
+    fn probe_needs_a_listener() {
+        let dir = script::socket_dir("probe");
+        let path = dir.join("ipc.sock");
+        assert!(!probe(&path));
+
+        let listener = UnixListener::bind(&path).unwrap();
+        assert!(probe(&path));
+
+        // A socket file left behind by a compositor that went away
+        drop(listener);
+        assert!(!probe(&path));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,253 @@
+//! Which widgets a bar's workspace box holds and how to get there from what
+//! it holds now, worked out on plain lists so `WorkspaceView::render` only
+//! applies the steps.
+
+use super::WorkspaceInfo;
+
+/// One widget in the workspace box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Slot {
+    Workspace(u64),
+    /// "+" button closing a group that can create workspaces
+    Create(u32),
+    /// Separator after a group when another one follows
+    Separator(u32),
+}
+
+/// One change to the box's children
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Step {
+    Remove(Slot),
+    /// Add a new widget after `after`, or first
+    Insert {
+        slot: Slot,
+        after: Option<Slot>,
+    },
+    /// Move a widget already shown to after `after`, or first
+    Move {
+        slot: Slot,
+        after: Option<Slot>,
+    },
+}
+
+/// The slots for `visible`, in order: each group's workspaces, then its "+"
+/// button and, when another group follows, a separator
+pub(super) fn slots(
+    visible: &[WorkspaceInfo],
+    can_create: impl Fn(u32) -> bool,
+    separators: bool,
+) -> Vec<Slot> {
+    let mut out = Vec::with_capacity(visible.len() + 2);
+    let close_group = |group: u32, more: bool, out: &mut Vec<Slot>| {
+        if can_create(group) {
+            out.push(Slot::Create(group));
+        }
+        if more && separators {
+            out.push(Slot::Separator(group));
+        }
+    };
+
+    let mut prev_group = None;
+    for info in visible {
+        if let Some(group) = prev_group.filter(|group| *group != info.group) {
+            close_group(group, true, &mut out);
+        }
+        prev_group = Some(info.group);
+        out.push(Slot::Workspace(info.id));
+    }
+    if let Some(group) = prev_group {
+        close_group(group, false, &mut out);
+    }
+    out
+}
+
+/// Steps from `shown` to `wanted`: removals first, then inserts and moves
+/// in order, leaving alone widgets that already follow the right one
+pub(super) fn diff(shown: &[Slot], wanted: &[Slot]) -> Vec<Step> {
+    let mut steps: Vec<Step> = shown
+        .iter()
+        .filter(|slot| !wanted.contains(slot))
+        .map(|slot| Step::Remove(*slot))
+        .collect();
+
+    // The box as the steps so far leave it; everything before `pos` is
+    // already in place
+    let mut current: Vec<Slot> = shown
+        .iter()
+        .copied()
+        .filter(|slot| wanted.contains(slot))
+        .collect();
+    let mut after = None;
+    for (pos, &slot) in wanted.iter().enumerate() {
+        match current.iter().position(|s| *s == slot) {
+            None => {
+                current.insert(pos, slot);
+                steps.push(Step::Insert { slot, after });
+            }
+            Some(at) if at != pos => {
+                current.remove(at);
+                current.insert(pos, slot);
+                steps.push(Step::Move { slot, after });
+            }
+            Some(_) => {}
+        }
+        after = Some(slot);
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mock::MockBackend;
+    use super::super::WorkspaceRequest;
+    use super::*;
+
+    /// What a box showing `shown` holds after `steps`
+    fn apply(shown: &[Slot], steps: &[Step]) -> Vec<Slot> {
+        let mut children = shown.to_vec();
+        let place = |children: &mut Vec<Slot>, slot: Slot, after: Option<Slot>| {
+            let pos = after.map_or(0, |a| children.iter().position(|s| *s == a).unwrap() + 1);
+            children.insert(pos, slot);
+        };
+        for step in steps {
+            match *step {
+                Step::Remove(slot) => children.retain(|s| *s != slot),
+                Step::Insert { slot, after } => {
+                    assert!(!children.contains(&slot), "{slot:?} inserted twice");
+                    place(&mut children, slot, after);
+                }
+                Step::Move { slot, after } => {
+                    children.retain(|s| *s != slot);
+                    place(&mut children, slot, after);
+                }
+            }
+        }
+        children
+    }
+
+    /// Slots for the mock's snapshot with group 1 able to create and
+    /// separators on
+    fn layout(backend: &MockBackend) -> Vec<Slot> {
+        slots(&backend.snapshot(), |group| group == 1, true)
+    }
+
+    #[test]
+    fn groups_end_with_create_then_separator() {
+        use Slot::*;
+        let backend = MockBackend::new();
+        assert_eq!(
+            layout(&backend),
+            [
+                Workspace(1),
+                Workspace(2),
+                Workspace(3),
+                Create(1),
+                Separator(1),
+                Workspace(4),
+            ]
+        );
+        assert_eq!(
+            slots(&backend.snapshot(), |_| true, false),
+            [
+                Workspace(1),
+                Workspace(2),
+                Workspace(3),
+                Create(1),
+                Workspace(4),
+                Create(2),
+            ]
+        );
+        assert_eq!(slots(&[], |_| true, true), []);
+    }
+
+    #[test]
+    fn follows_successive_snapshots() {
+        use Slot::*;
+        let mut backend = MockBackend::new();
+        let mut shown = Vec::new();
+        let mut render = |backend: &MockBackend| {
+            let wanted = layout(backend);
+            let steps = diff(&shown, &wanted);
+            assert_eq!(apply(&shown, &steps), wanted);
+            shown = wanted;
+            steps
+        };
+
+        // Everything is new at first
+        let steps = render(&backend);
+        assert_eq!(steps.len(), 6);
+        assert!(steps.iter().all(|s| matches!(s, Step::Insert { .. })));
+
+        // Activation changes no widgets
+        backend.apply(WorkspaceRequest::Activate(2));
+        assert_eq!(render(&backend), []);
+
+        backend.apply(WorkspaceRequest::Create {
+            group: 1,
+            name: "web".to_string(),
+        });
+        assert_eq!(
+            render(&backend),
+            [Step::Insert {
+                slot: Workspace(5),
+                after: Some(Workspace(3)),
+            }]
+        );
+
+        backend.apply(WorkspaceRequest::Remove(2));
+        assert_eq!(render(&backend), [Step::Remove(Workspace(2))]);
+
+        // Emptying group 2 leaves no group to separate
+        backend.apply(WorkspaceRequest::Assign { id: 4, group: 1 });
+        assert_eq!(
+            render(&backend),
+            [
+                Step::Remove(Separator(1)),
+                Step::Move {
+                    slot: Workspace(4),
+                    after: Some(Workspace(3)),
+                },
+            ]
+        );
+
+        // Widgets are placed front to back, so moving the first one away
+        // moves everything it was in front of
+        backend.apply(WorkspaceRequest::Assign { id: 1, group: 2 });
+        assert_eq!(
+            render(&backend),
+            [
+                Step::Move {
+                    slot: Workspace(3),
+                    after: None,
+                },
+                Step::Move {
+                    slot: Workspace(4),
+                    after: Some(Workspace(3)),
+                },
+                Step::Move {
+                    slot: Workspace(5),
+                    after: Some(Workspace(4)),
+                },
+                Step::Move {
+                    slot: Create(1),
+                    after: Some(Workspace(5)),
+                },
+                Step::Insert {
+                    slot: Separator(1),
+                    after: Some(Create(1)),
+                },
+            ]
+        );
+        assert_eq!(
+            shown,
+            [
+                Workspace(3),
+                Workspace(4),
+                Workspace(5),
+                Create(1),
+                Separator(1),
+                Workspace(1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! In-memory workspaces for tests: navigation, hooks and layout run against
+//! it without a compositor.
+
+use tokio::sync::mpsc;
+
+use super::{GroupInfo, WorkspaceBackend, WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+
+/// Two groups with every capability; requests are applied to the list and
+/// answered with a new snapshot, like a compositor would
+pub(super) struct MockBackend {
+    groups: Vec<GroupInfo>,
+    workspaces: Vec<WorkspaceInfo>,
+    next_id: u64,
+}
+
+impl MockBackend {
+    pub(super) fn new() -> Self {
+        let mut backend = Self {
+            groups: (1..=2)
+                .map(|id| GroupInfo {
+                    id,
+                    outputs: Vec::new(),
+                    can_create: true,
+                })
+                .collect(),
+            workspaces: Vec::new(),
+            next_id: 1,
+        };
+        for (group, name) in [(1, "1"), (1, "2"), (1, "3"), (2, "4")] {
+            backend.create(group, name.to_string());
+        }
+        backend.activate(1);
+        backend.activate(4);
+        backend
+    }
+
+    fn create(&mut self, group: u32, name: String) {
+        self.workspaces.push(WorkspaceInfo {
+            id: self.next_id,
+            name,
+            index: 0,
+            group,
+            active: false,
+            focused: false,
+            urgent: false,
+            hidden: false,
+            outputs: Vec::new(),
+            can_remove: true,
+            can_assign: true,
+            windows: Vec::new(),
+        });
+        self.next_id += 1;
+    }
+
+    /// Make `id` the active workspace of its group
+    fn activate(&mut self, id: u64) {
+        let Some(group) = self.workspaces.iter().find(|w| w.id == id).map(|w| w.group) else {
+            return;
+        };
+        for ws in self.workspaces.iter_mut().filter(|w| w.group == group) {
+            ws.active = ws.id == id;
+        }
+    }
+
+    /// Keep one active workspace per group after one leaves it
+    fn fix_active(&mut self, group: u32) {
+        let members = self.workspaces.iter().filter(|w| w.group == group);
+        if let Some(first) = members.clone().next().map(|w| w.id) {
+            if !members.clone().any(|w| w.active) {
+                self.activate(first);
+            }
+        }
+    }
+
+    pub(super) fn apply(&mut self, request: WorkspaceRequest) {
+        match request {
+            WorkspaceRequest::Activate(id) => self.activate(id),
+            WorkspaceRequest::Create { group, name } => {
+                if self.groups.iter().any(|g| g.id == group) {
+                    self.create(group, name);
+                    self.fix_active(group);
+                }
+            }
+            WorkspaceRequest::Remove(id) => {
+                if let Some(pos) = self.workspaces.iter().position(|w| w.id == id) {
+                    let removed = self.workspaces.remove(pos);
+                    self.fix_active(removed.group);
+                }
+            }
+            WorkspaceRequest::Assign { id, group } => {
+                if !self.groups.iter().any(|g| g.id == group) {
+                    return;
+                }
+                if let Some(ws) = self.workspaces.iter_mut().find(|w| w.id == id) {
+                    let from = ws.group;
+                    ws.group = group;
+                    ws.active = false;
+                    self.fix_active(from);
+                    self.fix_active(group);
+                }
+            }
+        }
+    }
+
+    pub(super) fn snapshot(&self) -> Vec<WorkspaceInfo> {
+        let mut list = self.workspaces.clone();
+        list.sort_by_key(|w| (w.group, w.id));
+        let mut index = 0;
+        let mut group = None;
+        for ws in &mut list {
+            if group != Some(ws.group) {
+                group = Some(ws.group);
+                index = 0;
+            }
+            index += 1;
+            ws.index = index;
+        }
+        list
+    }
+
+    fn send(&self, event_tx: &mpsc::Sender<WorkspaceEvent>) -> bool {
+        event_tx
+            .blocking_send(WorkspaceEvent::Groups(self.groups.clone()))
+            .is_ok()
+            && event_tx
+                .blocking_send(WorkspaceEvent::Snapshot(self.snapshot()))
+                .is_ok()
+    }
+}
+
+impl WorkspaceBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn run(
+        mut self: Box<Self>,
+        event_tx: mpsc::Sender<WorkspaceEvent>,
+        mut request_rx: mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.send(&event_tx) {
+            return Ok(());
+        }
+        while let Some(request) = request_rx.blocking_recv() {
+            self.apply(request);
+            if !self.send(&event_tx) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (id, name, group, index, active) per workspace, in snapshot order
+    fn summary(backend: &MockBackend) -> Vec<(u64, String, u32, u32, bool)> {
+        backend
+            .snapshot()
+            .into_iter()
+            .map(|w| (w.id, w.name, w.group, w.index, w.active))
+            .collect()
+    }
+
+    fn row(
+        id: u64,
+        name: &str,
+        group: u32,
+        index: u32,
+        active: bool,
+    ) -> (u64, String, u32, u32, bool) {
+        (id, name.to_string(), group, index, active)
+    }
+
+    #[test]
+    fn starts_with_two_groups() {
+        let backend = MockBackend::new();
+        assert_eq!(
+            summary(&backend),
+            [
+                row(1, "1", 1, 1, true),
+                row(2, "2", 1, 2, false),
+                row(3, "3", 1, 3, false),
+                row(4, "4", 2, 1, true),
+            ]
+        );
+        assert!(backend.groups.iter().all(|g| g.can_create));
+    }
+
+    #[test]
+    fn activate_stays_within_the_group() {
+        let mut backend = MockBackend::new();
+        backend.apply(WorkspaceRequest::Activate(3));
+        let active: Vec<u64> = backend
+            .snapshot()
+            .iter()
+            .filter(|w| w.active)
+            .map(|w| w.id)
+            .collect();
+        assert_eq!(active, [3, 4]);
+    }
+
+    #[test]
+    fn create_and_remove() {
+        let mut backend = MockBackend::new();
+        backend.apply(WorkspaceRequest::Create {
+            group: 2,
+            name: "web".to_string(),
+        });
+        backend.apply(WorkspaceRequest::Create {
+            group: 9,
+            name: "lost".to_string(),
+        });
+        assert_eq!(summary(&backend)[4], row(5, "web", 2, 2, false));
+        assert_eq!(summary(&backend).len(), 5);
+
+        // Removing the active workspace hands it to the group's first one
+        backend.apply(WorkspaceRequest::Remove(4));
+        assert_eq!(summary(&backend)[3], row(5, "web", 2, 1, true));
+        backend.apply(WorkspaceRequest::Remove(42));
+        assert_eq!(summary(&backend).len(), 4);
+    }
+
+    #[test]
+    fn assign_moves_between_groups() {
+        let mut backend = MockBackend::new();
+        backend.apply(WorkspaceRequest::Assign { id: 1, group: 2 });
+        assert_eq!(
+            summary(&backend),
+            [
+                row(2, "2", 1, 1, true),
+                row(3, "3", 1, 2, false),
+                row(1, "1", 2, 1, false),
+                row(4, "4", 2, 2, true),
+            ]
+        );
+
+        backend.apply(WorkspaceRequest::Assign { id: 2, group: 7 });
+        assert_eq!(summary(&backend)[0], row(2, "2", 1, 1, true));
+    }
+}
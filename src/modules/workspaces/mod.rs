@@ -0,0 +1,828 @@
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+
+use crate::config::types::{OutputFilter, Position, WorkspacesBackend, WorkspacesConfig};
+use crate::widgets::popup_menu::PopupMenu;
+
+mod ext;
+mod hooks;
+mod hyprland;
+mod ipc;
+mod layout;
+#[cfg(test)]
+mod mock;
+mod nav;
+mod niri;
+mod sartwc;
+mod sway;
+
+#[derive(Debug, Clone, PartialEq)]
+struct WorkspaceInfo {
+    id: u64,
+    name: String,
+    index: u32,
+    group: u32,
+    active: bool,
+    /// Has keyboard focus; only the compositor IPC backends know this
+    focused: bool,
+    urgent: bool,
+    hidden: bool,
+    /// Connector names of the outputs the workspace's group is on
+    outputs: Vec<String>,
+    can_remove: bool,
+    /// Whether the workspace can be moved to another group
+    can_assign: bool,
+    /// App ids of the workspace's windows, empty when the backend can't tell
+    windows: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GroupInfo {
+    id: u32,
+    outputs: Vec<String>,
+    can_create: bool,
+}
+
+#[derive(Debug)]
+enum WorkspaceEvent {
+    /// Sent before the `Snapshot` it describes; backends without groups never
+    /// send it
+    Groups(Vec<GroupInfo>),
+    Snapshot(Vec<WorkspaceInfo>),
+    Unavailable(String),
+}
+
+#[derive(Debug, Clone)]
+enum WorkspaceRequest {
+    Activate(u64),
+    Create { group: u32, name: String },
+    Remove(u64),
+    Assign { id: u64, group: u32 },
+}
+
+/// A workspace's button, kept while the workspace is shown
+struct WorkspaceButton {
+    button: gtk::Button,
+    /// What the button currently shows
+    shown: Option<WorkspaceInfo>,
+}
+
+/// Turns watcher snapshots into workspace buttons for this bar
+struct WorkspaceView {
+    container: gtk::Box,
+    format: String,
+    name_icons: HashMap<String, String>,
+    window_icon_size: i32,
+    show_hidden: bool,
+    output_filter: OutputFilter,
+    group_separator: Option<String>,
+    request_tx: mpsc::Sender<WorkspaceRequest>,
+    menu: PopupMenu,
+    /// Connector name (e.g. "DP-1") of the monitor the bar is on
+    output: RefCell<Option<String>>,
+    groups: RefCell<Vec<GroupInfo>>,
+    /// Last snapshot from the watcher, before any filtering
+    snapshot: RefCell<Vec<WorkspaceInfo>>,
+    /// Workspaces that currently have a button, in order
+    entries: RefCell<Vec<WorkspaceInfo>>,
+    /// The workspace that was current before this one, for `back-and-forth`
+    previous: Cell<Option<u64>>,
+    /// Widgets reused across snapshots: buttons by workspace id, "+" buttons
+    /// and separators by the group they follow
+    buttons: RefCell<HashMap<u64, WorkspaceButton>>,
+    create_buttons: RefCell<HashMap<u32, gtk::Button>>,
+    separators: RefCell<HashMap<u32, gtk::Label>>,
+    /// What the container holds, in order
+    slots: RefCell<Vec<layout::Slot>>,
+    hooks: Rc<hooks::Hooks>,
+    /// Whether a snapshot arrived yet, so startup doesn't count as creation
+    primed: Cell<bool>,
+}
+
+impl WorkspaceView {
+    /// Whether a workspace belongs on this bar. Groups without a known
+    /// output (or a bar not mapped yet) are shown everywhere.
+    fn on_this_output(&self, info: &WorkspaceInfo) -> bool {
+        if self.output_filter == OutputFilter::All || info.outputs.is_empty() {
+            return true;
+        }
+        match self.output.borrow().as_deref() {
+            Some(output) => info.outputs.iter().any(|o| o == output),
+            None => true,
+        }
+    }
+
+    /// Store a new snapshot, redraw and run the hooks for what changed. There
+    /// is one bar, so hooks run for every workspace whatever its output.
+    fn update(self: &Rc<Self>, list: Vec<WorkspaceInfo>) {
+        let before = self.snapshot.replace(list);
+        self.render();
+        self.hooks
+            .changed(&before, &self.snapshot.borrow(), !self.primed.replace(true));
+    }
+
+    fn set_output(self: &Rc<Self>, output: Option<String>) {
+        if *self.output.borrow() == output {
+            return;
+        }
+        debug!("Workspaces: bar is on output {output:?}");
+        *self.output.borrow_mut() = output;
+        self.render();
+    }
+
+    fn send(&self, request: WorkspaceRequest) {
+        let tx = self.request_tx.clone();
+        glib::spawn_future_local(async move {
+            let _ = tx.send(request).await;
+        });
+    }
+
+    /// Run `f` on what navigation sees of this bar
+    fn with_targets<R>(&self, f: impl FnOnce(&nav::Targets) -> R) -> R {
+        let entries = self.entries.borrow();
+        let snapshot = self.snapshot.borrow();
+        let groups = self.groups.borrow();
+        let output = self.output.borrow();
+        f(&nav::Targets {
+            entries: &entries,
+            snapshot: &snapshot,
+            groups: &groups,
+            output: output
+                .as_deref()
+                .filter(|_| self.output_filter == OutputFilter::Current),
+            previous: self.previous.get(),
+        })
+    }
+
+    fn can_create(&self, group: u32) -> bool {
+        self.with_targets(|t| t.can_create(group))
+    }
+
+    fn current(&self) -> Option<WorkspaceInfo> {
+        self.with_targets(|t| t.current().cloned())
+    }
+
+    fn step(&self, delta: isize) -> Option<u64> {
+        self.with_targets(|t| t.step(delta))
+    }
+
+    fn create_button(self: &Rc<Self>, group: u32) -> gtk::Button {
+        let label = gtk::Label::new(Some("+"));
+        label.add_css_class("module-label");
+
+        let button = gtk::Button::new();
+        button.set_child(Some(&label));
+        button.add_css_class("workspace-button");
+        button.add_css_class("workspace-create");
+
+        let view = Rc::downgrade(self);
+        button.connect_clicked(move |_| {
+            if let Some(view) = view.upgrade() {
+                let name = view.with_targets(|t| t.next_name(group));
+                view.send(WorkspaceRequest::Create { group, name });
+            }
+        });
+        button
+    }
+
+    /// The widget for a slot, making "+" buttons and separators as needed.
+    /// Workspace buttons are made by `render` before it asks.
+    fn slot_widget(self: &Rc<Self>, slot: layout::Slot) -> gtk::Widget {
+        match slot {
+            layout::Slot::Workspace(id) => self.buttons.borrow()[&id].button.clone().upcast(),
+            layout::Slot::Create(group) => self
+                .create_buttons
+                .borrow_mut()
+                .entry(group)
+                .or_insert_with(|| self.create_button(group))
+                .clone()
+                .upcast(),
+            layout::Slot::Separator(group) => self
+                .separators
+                .borrow_mut()
+                .entry(group)
+                .or_insert_with(|| {
+                    let label = gtk::Label::new(self.group_separator.as_deref());
+                    label.add_css_class("workspace-separator");
+                    label
+                })
+                .clone()
+                .upcast(),
+        }
+    }
+
+    /// Forget a slot's widget, returning it
+    fn take_slot_widget(&self, slot: layout::Slot) -> Option<gtk::Widget> {
+        match slot {
+            layout::Slot::Workspace(id) => self
+                .buttons
+                .borrow_mut()
+                .remove(&id)
+                .map(|b| b.button.upcast()),
+            layout::Slot::Create(group) => self
+                .create_buttons
+                .borrow_mut()
+                .remove(&group)
+                .map(|b| b.upcast()),
+            layout::Slot::Separator(group) => self
+                .separators
+                .borrow_mut()
+                .remove(&group)
+                .map(|l| l.upcast()),
+        }
+    }
+
+    fn workspace_button(self: &Rc<Self>, id: u64) -> gtk::Button {
+        let button = gtk::Button::new();
+        button.add_css_class("workspace-button");
+
+        let tx = self.request_tx.clone();
+        button.connect_clicked(move |_| {
+            let tx = tx.clone();
+            glib::spawn_future_local(async move {
+                let _ = tx.send(WorkspaceRequest::Activate(id)).await;
+            });
+        });
+
+        // Looked up on click, since the button outlives any one snapshot
+        let right_click = gtk::GestureClick::new();
+        right_click.set_button(3);
+        let view = Rc::downgrade(self);
+        right_click.connect_released(move |gesture, _, _, _| {
+            let Some(view) = view.upgrade() else {
+                return;
+            };
+            let info = view.entries.borrow().iter().find(|w| w.id == id).cloned();
+            let button = gesture.widget().and_downcast::<gtk::Button>();
+            if let (Some(info), Some(button)) = (info, button) {
+                view.show_menu(&info, &button);
+            }
+        });
+        button.add_controller(right_click);
+        button
+    }
+
+    /// Bring a button up to date with `info`, touching only what changed
+    fn update_button(&self, entry: &mut WorkspaceButton, info: &WorkspaceInfo) {
+        let button = &entry.button;
+        let label_changed = entry.shown.as_ref().is_none_or(|shown| {
+            shown.name != info.name
+                || shown.index != info.index
+                || shown.group != info.group
+                || shown.windows != info.windows
+        });
+        if label_changed {
+            button.set_child(Some(&self.button_content(info)));
+        }
+        for (class, on) in [
+            ("active", info.active),
+            ("urgent", info.urgent),
+            ("hidden", info.hidden),
+        ] {
+            if on {
+                button.add_css_class(class);
+            } else {
+                button.remove_css_class(class);
+            }
+        }
+        entry.shown = Some(info.clone());
+    }
+
+    /// Replace the buttons with a placeholder explaining why there are none
+    fn show_unavailable(&self, reason: &str) {
+        self.buttons.borrow_mut().clear();
+        self.create_buttons.borrow_mut().clear();
+        self.separators.borrow_mut().clear();
+        self.slots.borrow_mut().clear();
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+        let label = gtk::Label::new(Some("WS"));
+        label.add_css_class("module-label");
+        self.container.append(&label);
+        super::set_tooltip_text(self.container.clone(), Some(reason));
+    }
+
+    /// Right-click menu offering whatever the compositor allows for `info`
+    fn show_menu(&self, info: &WorkspaceInfo, anchor: &gtk::Button) {
+        let mut actions = Vec::new();
+        if info.can_remove {
+            actions.push(("Remove".to_string(), WorkspaceRequest::Remove(info.id)));
+        }
+        if info.can_assign {
+            for group in self.groups.borrow().iter().filter(|g| g.id != info.group) {
+                let label = if group.outputs.is_empty() {
+                    format!("Move to group {}", group.id)
+                } else {
+                    format!("Move to {}", group.outputs.join(", "))
+                };
+                let request = WorkspaceRequest::Assign {
+                    id: info.id,
+                    group: group.id,
+                };
+                actions.push((label, request));
+            }
+        }
+        if actions.is_empty() {
+            return;
+        }
+
+        self.menu.clear();
+        self.menu
+            .add_header(&format_label(&self.format, &self.name_icons, info));
+        for (label, request) in actions {
+            let tx = self.request_tx.clone();
+            self.menu.add_item(&label, move || {
+                let tx = tx.clone();
+                let request = request.clone();
+                glib::spawn_future_local(async move {
+                    let _ = tx.send(request).await;
+                });
+            });
+        }
+        self.menu.popup_at(anchor);
+    }
+
+    /// The bar answers every `workspace` command; `next` and `prev` stay on
+    /// its output through `with_targets`
+    fn handle_request(&self, request: crate::ipc::Request) {
+        let command = request
+            .command
+            .strip_prefix("workspace")
+            .unwrap_or_default();
+        request.reply(self.handle_command(command.trim()));
+    }
+
+    /// Handle `ferritebar msg workspace <command>`
+    fn handle_command(&self, command: &str) -> Result<(), String> {
+        let request = self.with_targets(|t| t.command(command))?;
+        self.send(request);
+        Ok(())
+    }
+
+    /// Button label; `{windows}` splits it around one icon per application
+    fn button_content(&self, info: &WorkspaceInfo) -> gtk::Widget {
+        let Some((before, after)) = self.format.split_once("{windows}") else {
+            let label = gtk::Label::new(Some(&format_label(&self.format, &self.name_icons, info)));
+            label.add_css_class("module-label");
+            return label.upcast();
+        };
+
+        let content = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let append_text = |format: &str| {
+            let text = expand_placeholders(format, Some(&self.name_icons), info);
+            let text = text.trim();
+            if !text.is_empty() {
+                let label = gtk::Label::new(Some(text));
+                label.add_css_class("module-label");
+                content.append(&label);
+            }
+        };
+
+        append_text(before);
+        let mut seen: Vec<&str> = Vec::new();
+        for app_id in &info.windows {
+            if app_id.is_empty() || seen.contains(&app_id.as_str()) {
+                continue;
+            }
+            seen.push(app_id);
+            // A cache hit: `build` resolved it before the snapshot got here
+            let icon = gtk::Image::from_icon_name(&super::taskbar::resolve_icon_name(app_id));
+            icon.set_pixel_size(self.window_icon_size);
+            icon.add_css_class("workspace-window");
+            content.append(&icon);
+        }
+        append_text(after);
+
+        // Keep the button clickable when there is nothing to show
+        if content.first_child().is_none() {
+            let label = gtk::Label::new(Some(&format_label("", &self.name_icons, info)));
+            label.add_css_class("module-label");
+            content.append(&label);
+        }
+        content.upcast()
+    }
+
+    fn render(self: &Rc<Self>) {
+        let visible: Vec<WorkspaceInfo> = self
+            .snapshot
+            .borrow()
+            .iter()
+            .filter(|info| (self.show_hidden || !info.hidden) && self.on_this_output(info))
+            .cloned()
+            .collect();
+
+        let wanted = layout::slots(
+            &visible,
+            |group| self.can_create(group),
+            self.group_separator.is_some(),
+        );
+
+        // Auto-hide when only 1 workspace exists, unless more can be created
+        let creatable = wanted.iter().any(|s| matches!(s, layout::Slot::Create(_)));
+        self.container.set_visible(visible.len() > 1 || creatable);
+
+        // Only workspaces that appeared get a new button
+        for info in &visible {
+            let mut buttons = self.buttons.borrow_mut();
+            let entry = buttons.entry(info.id).or_insert_with(|| WorkspaceButton {
+                button: self.workspace_button(info.id),
+                shown: None,
+            });
+            self.update_button(entry, info);
+        }
+
+        let shown = self.slots.replace(wanted.clone());
+        if shown.is_empty() {
+            // Nothing laid out yet, but maybe the unavailable placeholder
+            while let Some(child) = self.container.first_child() {
+                self.container.remove(&child);
+            }
+        }
+        for step in layout::diff(&shown, &wanted) {
+            match step {
+                layout::Step::Remove(slot) => {
+                    if let Some(widget) = self.take_slot_widget(slot) {
+                        self.container.remove(&widget);
+                    }
+                }
+                layout::Step::Insert { slot, after } => {
+                    let after = after.map(|s| self.slot_widget(s));
+                    self.container
+                        .insert_child_after(&self.slot_widget(slot), after.as_ref());
+                }
+                layout::Step::Move { slot, after } => {
+                    let after = after.map(|s| self.slot_widget(s));
+                    self.container
+                        .reorder_child_after(&self.slot_widget(slot), after.as_ref());
+                }
+            }
+        }
+
+        let before = self.current();
+        *self.entries.borrow_mut() = visible;
+        if let (Some(before), Some(now)) = (before, self.current()) {
+            if before.id != now.id {
+                self.previous.set(Some(before.id));
+            }
+        }
+    }
+}
+
+pub fn build(
+    config: &WorkspacesConfig,
+    app: &gtk::Application,
+    bar_position: Position,
+    bar_height: u32,
+    bar_edge_margin: i32,
+) -> gtk::Widget {
+    let (event_tx, event_rx) = mpsc::channel::<WorkspaceEvent>(8);
+    let (backend_tx, mut backend_rx) = mpsc::channel::<WorkspaceEvent>(8);
+    let (request_tx, request_rx) = mpsc::channel::<WorkspaceRequest>(8);
+
+    // Resolve window icons before snapshots reach the GTK thread: the first
+    // lookup of an app scans .desktop files
+    let wants_icons = config.format.contains("{windows}");
+    crate::spawn(async move {
+        while let Some(event) = backend_rx.recv().await {
+            let event = match event {
+                WorkspaceEvent::Snapshot(list) if wants_icons => {
+                    let resolved = tokio::task::spawn_blocking(move || {
+                        for app_id in list.iter().flat_map(|w| &w.windows) {
+                            super::taskbar::resolve_icon_name(app_id);
+                        }
+                        list
+                    })
+                    .await;
+                    match resolved {
+                        Ok(list) => WorkspaceEvent::Snapshot(list),
+                        Err(e) => {
+                            error!("Workspace icon lookup panicked: {e}");
+                            continue;
+                        }
+                    }
+                }
+                event => event,
+            };
+            if event_tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Spawn the workspace watcher on a blocking thread
+    let backend = config.backend;
+    crate::spawn(async move {
+        let result = tokio::task::spawn_blocking(move || {
+            if let Err(e) = run_workspace_watcher(backend, backend_tx, request_rx) {
+                error!("Workspace watcher failed: {e}");
+            }
+        })
+        .await;
+
+        if let Err(e) = result {
+            error!("Workspace watcher task panicked: {e}");
+        }
+    });
+
+    let container = gtk::Box::new(gtk::Orientation::Horizontal, 2);
+    container.add_css_class("module");
+    container.add_css_class("workspaces");
+
+    let enable_scroll = config.scroll;
+
+    let view = Rc::new(WorkspaceView {
+        container: container.clone(),
+        format: config.format.clone(),
+        name_icons: config.name_icons.clone(),
+        window_icon_size: config.window_icon_size,
+        show_hidden: config.show_hidden,
+        output_filter: config.output_filter,
+        group_separator: config.group_separator.clone(),
+        request_tx: request_tx.clone(),
+        menu: PopupMenu::new(
+            app,
+            "ferritebar-workspace-menu",
+            bar_position,
+            bar_height,
+            bar_edge_margin,
+        ),
+        output: RefCell::new(None),
+        groups: RefCell::new(Vec::new()),
+        snapshot: RefCell::new(Vec::new()),
+        entries: RefCell::new(Vec::new()),
+        previous: Cell::new(None),
+        buttons: RefCell::new(HashMap::new()),
+        create_buttons: RefCell::new(HashMap::new()),
+        separators: RefCell::new(HashMap::new()),
+        slots: RefCell::new(Vec::new()),
+        hooks: hooks::Hooks::new(config),
+        primed: Cell::new(false),
+    });
+
+    // Follow the monitor the bar window is placed on; also decides which
+    // bar answers IPC commands
+    {
+        let view_ref = view.clone();
+        container.connect_realize(move |widget| {
+            let Some(surface) = widget.native().and_then(|native| native.surface()) else {
+                return;
+            };
+            if let Some(monitor) = widget.display().monitor_at_surface(&surface) {
+                view_ref.set_output(monitor.connector().map(|c| c.to_string()));
+            }
+            let view = view_ref.clone();
+            surface.connect_enter_monitor(move |_, monitor| {
+                view.set_output(monitor.connector().map(|c| c.to_string()));
+            });
+        });
+    }
+
+    let container_ref = container.clone();
+    let view_ref = view.clone();
+
+    super::recv_on_main_thread(event_rx, move |event| match event {
+        WorkspaceEvent::Groups(groups) => {
+            *view_ref.groups.borrow_mut() = groups;
+        }
+        WorkspaceEvent::Unavailable(reason) => {
+            container_ref.remove_css_class("active");
+            view_ref.show_unavailable(&reason);
+        }
+        WorkspaceEvent::Snapshot(list) => view_ref.update(list),
+    });
+
+    if enable_scroll {
+        let view_ref = view.clone();
+        let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        scroll.connect_scroll(move |_, _dx, dy| {
+            let Some(id) = view_ref.step(if dy > 0.0 { 1 } else { -1 }) else {
+                return glib::Propagation::Proceed;
+            };
+            view_ref.send(WorkspaceRequest::Activate(id));
+            glib::Propagation::Stop
+        });
+        container.add_controller(scroll);
+    }
+
+    // IPC: `ferritebar msg workspace <command>`, answered with an error
+    // when the target doesn't exist
+    let view_ipc = view.clone();
+    super::recv_on_main_thread(crate::ipc::handle("workspace"), move |request| {
+        view_ipc.handle_request(request)
+    });
+
+    debug!("Workspaces module created");
+    container.upcast()
+}
+
+/// `name_icons` entry for a workspace: by name, then index, then "default",
+/// else what `{name}` would show
+fn workspace_icon(icons: &HashMap<String, String>, info: &WorkspaceInfo) -> String {
+    let index = info.index.to_string();
+    icons
+        .get(&info.name)
+        .or_else(|| icons.get(&index))
+        .or_else(|| icons.get("default"))
+        .cloned()
+        .unwrap_or_else(|| {
+            if info.name.is_empty() {
+                index
+            } else {
+                info.name.clone()
+            }
+        })
+}
+
+/// Expand `{name}`, `{index}` and `{group}` in `format`. Labels pass their
+/// `icons`, which also expands `{icon}` and drops `{windows}`; hook commands
+/// pass `None` and keep those two as written.
+fn expand_placeholders(
+    format: &str,
+    icons: Option<&HashMap<String, String>>,
+    info: &WorkspaceInfo,
+) -> String {
+    let mut parts = format.split('{');
+    let mut out = String::with_capacity(format.len());
+    out.push_str(parts.next().unwrap_or_default());
+    for part in parts {
+        if let Some(rest) = part.strip_prefix("name}") {
+            if info.name.is_empty() {
+                out.push_str(&info.index.to_string());
+            } else {
+                out.push_str(&info.name);
+            }
+            out.push_str(rest);
+        } else if let Some(rest) = part.strip_prefix("index}") {
+            out.push_str(&info.index.to_string());
+            out.push_str(rest);
+        } else if let Some(rest) = part.strip_prefix("group}") {
+            out.push_str(&info.group.to_string());
+            out.push_str(rest);
+        } else if let (Some(icons), Some(rest)) = (icons, part.strip_prefix("icon}")) {
+            out.push_str(&workspace_icon(icons, info));
+            out.push_str(rest);
+        } else if let (Some(_), Some(rest)) = (icons, part.strip_prefix("windows}")) {
+            // Drawn as images by `button_content`; dropped from plain text
+            out.push_str(rest);
+        } else {
+            // Not a placeholder: keep the brace, e.g. `${HOME}` in a command
+            out.push('{');
+            out.push_str(part);
+        }
+    }
+    out
+}
+
+fn format_label(format: &str, icons: &HashMap<String, String>, info: &WorkspaceInfo) -> String {
+    let out = expand_placeholders(format, Some(icons), info);
+    if out.is_empty() {
+        if info.name.is_empty() {
+            info.index.to_string()
+        } else {
+            info.name.clone()
+        }
+    } else {
+        out
+    }
+}
+
+// ---- Workspace backends (run on a blocking thread) ----
+
+/// A source of workspace snapshots that carries out requests: `run` sends
+/// `WorkspaceEvent`s on `event_tx` and applies what arrives on `request_rx`
+/// until the widget goes away.
+trait WorkspaceBackend {
+    /// Shown in logs
+    fn name(&self) -> &'static str;
+
+    fn run(
+        self: Box<Self>,
+        event_tx: mpsc::Sender<WorkspaceEvent>,
+        request_rx: mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// The backend `kind` names, or why it can't be used here
+fn select_backend(kind: WorkspacesBackend) -> Result<Box<dyn WorkspaceBackend>, String> {
+    match kind {
+        // Compositor IPC reports more than ext-workspace (urgency, windows),
+        // so it wins when the session advertises one that answers
+        WorkspacesBackend::Auto => {
+            if let Some(socket_path) = sway::socket_path().filter(|p| ipc::probe(p)) {
+                return Ok(Box::new(sway::SwayBackend { socket_path }));
+            }
+            if let Some(dir) =
+                hyprland::socket_dir().filter(|d| ipc::probe(&hyprland::event_socket(d)))
+            {
+                return Ok(Box::new(hyprland::HyprlandBackend { dir }));
+            }
+            if let Some(socket_path) = niri::socket_path().filter(|p| ipc::probe(p)) {
+                return Ok(Box::new(niri::NiriBackend { socket_path }));
+            }
+            match ext::ExtBackend::connect() {
+                Ok(backend) => return Ok(Box::new(backend)),
+                Err(e) => debug!("Workspaces: ext-workspace-v1 unavailable: {e}"),
+            }
+            match sartwc::sartwc_ipc_socket_path() {
+                Ok(socket_path) if socket_path.exists() => {
+                    Ok(Box::new(sartwc::SartwcBackend { socket_path }))
+                }
+                _ => Err("No workspace protocol or compositor IPC found".to_string()),
+            }
+        }
+        WorkspacesBackend::Ext => match ext::ExtBackend::connect() {
+            Ok(backend) => Ok(Box::new(backend)),
+            Err(e) => Err(format!("ext-workspace-v1 unavailable: {e}")),
+        },
+        WorkspacesBackend::Sartwc => match sartwc::sartwc_ipc_socket_path() {
+            Ok(socket_path) => Ok(Box::new(sartwc::SartwcBackend { socket_path })),
+            Err(e) => Err(e.to_string()),
+        },
+        WorkspacesBackend::Sway => match sway::socket_path() {
+            Some(socket_path) => Ok(Box::new(sway::SwayBackend { socket_path })),
+            None => Err("SWAYSOCK is not set".to_string()),
+        },
+        WorkspacesBackend::Hyprland => match hyprland::socket_dir() {
+            Some(dir) => Ok(Box::new(hyprland::HyprlandBackend { dir })),
+            None => Err("HYPRLAND_INSTANCE_SIGNATURE is not set".to_string()),
+        },
+        WorkspacesBackend::Niri => match niri::socket_path() {
+            Some(socket_path) => Ok(Box::new(niri::NiriBackend { socket_path })),
+            None => Err("NIRI_SOCKET is not set".to_string()),
+        },
+        #[cfg(test)]
+        WorkspacesBackend::Mock => Ok(Box::new(mock::MockBackend::new())),
+    }
+}
+
+fn run_workspace_watcher(
+    kind: WorkspacesBackend,
+    event_tx: mpsc::Sender<WorkspaceEvent>,
+    request_rx: mpsc::Receiver<WorkspaceRequest>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let backend = match select_backend(kind) {
+        Ok(backend) => backend,
+        Err(reason) => {
+            warn!("Workspaces unavailable: {reason}");
+            let _ = event_tx.blocking_send(WorkspaceEvent::Unavailable(reason));
+            return Ok(());
+        }
+    };
+    debug!("Workspaces: using the {} backend", backend.name());
+    backend.run(event_tx, request_rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_and_commands_share_placeholders() {
+        let info = WorkspaceInfo {
+            index: 3,
+            ..test_workspace(7, "web", 2)
+        };
+        let icons = HashMap::from([("web".to_string(), "\u{f269}".to_string())]);
+
+        assert_eq!(
+            format_label("{index}:{name} {icon}{windows}", &icons, &info),
+            "3:web \u{f269}"
+        );
+        assert_eq!(
+            expand_placeholders("sync {index} {name} {group} {icon}{windows}", None, &info),
+            "sync 3 web 2 {icon}{windows}"
+        );
+
+        // Unnamed workspaces go by index; other braces are left alone
+        let unnamed = test_workspace(4, "", 1);
+        assert_eq!(
+            expand_placeholders("echo ${HOME} {name} {", None, &unnamed),
+            "echo ${HOME} 4 {"
+        );
+        assert_eq!(format_label("", &icons, &unnamed), "4");
+    }
+}
+
+/// A plain workspace for tests: inactive, every capability
+#[cfg(test)]
+fn test_workspace(id: u64, name: &str, group: u32) -> WorkspaceInfo {
+    WorkspaceInfo {
+        id,
+        name: name.to_string(),
+        index: id as u32,
+        group,
+        active: false,
+        focused: false,
+        urgent: false,
+        hidden: false,
+        outputs: Vec::new(),
+        can_remove: true,
+        can_assign: true,
+        windows: Vec::new(),
+    }
+}
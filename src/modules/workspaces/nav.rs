@@ -0,0 +1,353 @@
+//! Navigation over a bar's workspaces: the current workspace, scroll
+//! steps and `ferritebar msg workspace` commands, kept apart from the
+//! widgets so they work on plain lists.
+
+use super::{GroupInfo, WorkspaceInfo, WorkspaceRequest};
+
+/// What a bar's navigation sees
+pub(super) struct Targets<'a> {
+    /// Workspaces that have a button on this bar, in order
+    pub(super) entries: &'a [WorkspaceInfo],
+    /// Last snapshot from the watcher, before any filtering
+    pub(super) snapshot: &'a [WorkspaceInfo],
+    pub(super) groups: &'a [GroupInfo],
+    /// Output the bar is on when it only shows that output's groups
+    pub(super) output: Option<&'a str>,
+    /// The workspace that was current before this one
+    pub(super) previous: Option<u64>,
+}
+
+impl Targets<'_> {
+    /// The focused workspace if it's on this bar, else the first active one
+    pub(super) fn current(&self) -> Option<&WorkspaceInfo> {
+        self.entries
+            .iter()
+            .find(|w| w.focused)
+            .or_else(|| self.entries.iter().find(|w| w.active))
+    }
+
+    /// The workspace `delta` buttons away from the current one, wrapping
+    /// around
+    pub(super) fn step(&self, delta: isize) -> Option<u64> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let pos = self
+            .current()
+            .and_then(|c| self.entries.iter().position(|w| w.id == c.id))
+            .unwrap_or(0);
+        let next = (pos as isize + delta).rem_euclid(self.entries.len() as isize);
+        Some(self.entries[next as usize].id)
+    }
+
+    /// The group this bar works on: the one holding its active workspace,
+    /// else the first group on its output
+    pub(super) fn current_group(&self) -> Option<u32> {
+        if let Some(active) = self.entries.iter().find(|w| w.active) {
+            return Some(active.group);
+        }
+        self.groups
+            .iter()
+            .find(|g| {
+                g.outputs.is_empty()
+                    || self
+                        .output
+                        .is_none_or(|output| g.outputs.iter().any(|o| o == output))
+            })
+            .map(|g| g.id)
+    }
+
+    pub(super) fn can_create(&self, group: u32) -> bool {
+        self.groups.iter().any(|g| g.id == group && g.can_create)
+    }
+
+    /// Name for a new workspace: one past the group's current count
+    pub(super) fn next_name(&self, group: u32) -> String {
+        let count = self.snapshot.iter().filter(|w| w.group == group).count();
+        (count + 1).to_string()
+    }
+
+    /// The request for `ferritebar msg workspace <command>`. Targets are
+    /// looked up among this bar's buttons, so hidden workspaces and other
+    /// outputs' groups only count when they are shown.
+    pub(super) fn command(&self, command: &str) -> Result<WorkspaceRequest, String> {
+        let (verb, arg) = match command.split_once(' ') {
+            Some((verb, arg)) => (verb, arg.trim()),
+            None => (command, ""),
+        };
+        let find = |name: &str| {
+            self.entries
+                .iter()
+                .find(|w| w.name == name)
+                .ok_or_else(|| format!("no workspace {name:?}"))
+        };
+        // Named target, or this bar's current workspace
+        let target = |name: &str| {
+            if name.is_empty() {
+                self.current()
+                    .ok_or_else(|| "no current workspace".to_string())
+            } else {
+                find(name)
+            }
+        };
+
+        let request = match verb {
+            "next" | "prev" => {
+                let delta = if verb == "next" { 1 } else { -1 };
+                WorkspaceRequest::Activate(self.step(delta).ok_or("no workspaces")?)
+            }
+            "goto" => {
+                let index: u32 = arg
+                    .parse()
+                    .map_err(|_| format!("not a workspace index: {arg:?}"))?;
+                // Indexes restart in each group; prefer the current one
+                let group = self.current_group();
+                let info = self
+                    .entries
+                    .iter()
+                    .find(|w| w.index == index && Some(w.group) == group)
+                    .or_else(|| self.entries.iter().find(|w| w.index == index))
+                    .ok_or_else(|| format!("no workspace {index}"))?;
+                WorkspaceRequest::Activate(info.id)
+            }
+            "goto-name" => WorkspaceRequest::Activate(find(arg)?.id),
+            "back-and-forth" => {
+                let id = self
+                    .previous
+                    .filter(|id| self.entries.iter().any(|w| w.id == *id))
+                    .ok_or("no previous workspace")?;
+                WorkspaceRequest::Activate(id)
+            }
+            "create" => {
+                let group = self
+                    .current_group()
+                    .filter(|g| self.can_create(*g))
+                    .ok_or("compositor does not allow creating workspaces here")?;
+                let name = if arg.is_empty() {
+                    self.next_name(group)
+                } else {
+                    arg.to_string()
+                };
+                WorkspaceRequest::Create { group, name }
+            }
+            "remove" => {
+                let info = target(arg)?;
+                if !info.can_remove {
+                    return Err("compositor does not allow removing this workspace".into());
+                }
+                WorkspaceRequest::Remove(info.id)
+            }
+            "move-to-group" => {
+                let (name, group) = arg.rsplit_once(' ').unwrap_or(("", arg));
+                let group = self
+                    .groups
+                    .iter()
+                    .find(|g| g.id.to_string() == group || g.outputs.iter().any(|o| o == group))
+                    .map(|g| g.id)
+                    .ok_or_else(|| format!("no workspace group {group:?}"))?;
+                let info = target(name)?;
+                if !info.can_assign {
+                    return Err("compositor does not allow moving this workspace".into());
+                }
+                WorkspaceRequest::Assign { id: info.id, group }
+            }
+            _ => return Err(format!("unknown workspace command {verb:?}")),
+        };
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mock::MockBackend;
+    use super::super::test_workspace;
+    use super::*;
+
+    fn groups() -> Vec<GroupInfo> {
+        vec![
+            GroupInfo {
+                id: 1,
+                outputs: vec!["DP-1".to_string()],
+                can_create: true,
+            },
+            GroupInfo {
+                id: 2,
+                outputs: vec!["HDMI-A-1".to_string()],
+                can_create: false,
+            },
+        ]
+    }
+
+    /// "1", "2", "3" in group 1 with `active` shown, "4" in group 2
+    fn entries(active: u64) -> Vec<WorkspaceInfo> {
+        let mut list = vec![
+            test_workspace(1, "1", 1),
+            test_workspace(2, "2", 1),
+            test_workspace(3, "3", 1),
+            test_workspace(4, "4", 2),
+        ];
+        list[3].index = 1;
+        for ws in &mut list {
+            ws.active = ws.id == active;
+        }
+        list
+    }
+
+    fn targets<'a>(entries: &'a [WorkspaceInfo], groups: &'a [GroupInfo]) -> Targets<'a> {
+        Targets {
+            entries,
+            snapshot: entries,
+            groups,
+            output: Some("DP-1"),
+            previous: None,
+        }
+    }
+
+    #[test]
+    fn step_wraps_both_ways() {
+        let groups = groups();
+        let middle = entries(2);
+        assert_eq!(targets(&middle, &groups).step(1), Some(3));
+        assert_eq!(targets(&middle, &groups).step(-1), Some(1));
+        assert_eq!(targets(&middle, &groups).step(-6), Some(4));
+
+        let last = entries(4);
+        assert_eq!(targets(&last, &groups).step(1), Some(1));
+        let first = entries(1);
+        assert_eq!(targets(&first, &groups).step(-1), Some(4));
+
+        // Nothing current: count from the first button
+        let none = entries(0);
+        assert_eq!(targets(&none, &groups).step(1), Some(2));
+        assert_eq!(targets(&[], &groups).step(1), None);
+        assert_eq!(targets(&[], &groups).step(-1), None);
+    }
+
+    #[test]
+    fn focus_wins_over_active() {
+        let groups = groups();
+        let mut list = entries(1);
+        list[3].active = true;
+        list[3].focused = true;
+        let targets = targets(&list, &groups);
+        assert_eq!(targets.current().map(|w| w.id), Some(4));
+        assert_eq!(targets.step(1), Some(1));
+    }
+
+    #[test]
+    fn commands_resolve_to_requests() {
+        let groups = groups();
+        let list = entries(4);
+        let mut targets = targets(&list, &groups);
+        targets.previous = Some(2);
+
+        let ok = |command: &str| format!("{:?}", targets.command(command).unwrap());
+        assert_eq!(ok("next"), "Activate(1)");
+        assert_eq!(ok("prev"), "Activate(3)");
+        // Index 1 exists in both groups; the active one's group wins
+        assert_eq!(ok("goto 1"), "Activate(4)");
+        assert_eq!(ok("goto 3"), "Activate(3)");
+        assert_eq!(ok("goto-name 2"), "Activate(2)");
+        assert_eq!(ok("back-and-forth"), "Activate(2)");
+        assert_eq!(ok("remove"), "Remove(4)");
+        assert_eq!(ok("move-to-group 3 HDMI-A-1"), "Assign { id: 3, group: 2 }");
+        assert_eq!(ok("move-to-group 1"), "Assign { id: 4, group: 1 }");
+
+        let list = entries(1);
+        let targets = Targets {
+            entries: &list,
+            ..targets
+        };
+        assert_eq!(
+            format!("{:?}", targets.command("create").unwrap()),
+            r#"Create { group: 1, name: "4" }"#
+        );
+        assert_eq!(
+            format!("{:?}", targets.command("create web").unwrap()),
+            r#"Create { group: 1, name: "web" }"#
+        );
+    }
+
+    #[test]
+    fn command_errors() {
+        let groups = groups();
+        let mut list = entries(4);
+        list[3].can_remove = false;
+        list[3].can_assign = false;
+        let shown = targets(&list, &groups);
+
+        let cases = [
+            ("", r#"unknown workspace command """#),
+            ("jump 1", r#"unknown workspace command "jump""#),
+            ("goto", r#"not a workspace index: """#),
+            ("goto two", r#"not a workspace index: "two""#),
+            ("goto 9", "no workspace 9"),
+            ("goto-name web", r#"no workspace "web""#),
+            ("back-and-forth", "no previous workspace"),
+            (
+                "create",
+                "compositor does not allow creating workspaces here",
+            ),
+            (
+                "remove",
+                "compositor does not allow removing this workspace",
+            ),
+            ("remove web", r#"no workspace "web""#),
+            ("move-to-group 3", r#"no workspace group "3""#),
+            ("move-to-group web 1", r#"no workspace "web""#),
+            (
+                "move-to-group 1",
+                "compositor does not allow moving this workspace",
+            ),
+        ];
+        for (command, error) in cases {
+            assert_eq!(shown.command(command).unwrap_err(), error, "{command}");
+        }
+
+        assert_eq!(
+            targets(&[], &groups).command("next").unwrap_err(),
+            "no workspaces"
+        );
+        assert_eq!(
+            targets(&[], &groups).command("remove").unwrap_err(),
+            "no current workspace"
+        );
+    }
+
+    #[test]
+    fn commands_drive_the_mock() {
+        let groups = groups();
+        let mut backend = MockBackend::new();
+        // Applies the command's request and returns the active workspaces
+        let mut run = |command: &str| {
+            let list = backend.snapshot();
+            let request = targets(&list, &groups).command(command).unwrap();
+            backend.apply(request);
+            let active: Vec<u64> = backend
+                .snapshot()
+                .iter()
+                .filter(|w| w.active)
+                .map(|w| w.id)
+                .collect();
+            active
+        };
+
+        let cases = [
+            ("next", [2, 4]),
+            ("next", [3, 4]),
+            ("prev", [2, 4]),
+            // Makes "4" in group 1 (id 5), leaving the active one alone
+            ("create", [2, 4]),
+            ("prev", [1, 4]),
+            ("remove", [2, 4]),
+            // Two workspaces are named "4" now; the first button wins
+            ("goto-name 4", [5, 4]),
+            ("move-to-group 2", [2, 4]),
+        ];
+        for (command, active) in cases {
+            assert_eq!(run(command), active, "{command}");
+        }
+        let ids: Vec<u64> = backend.snapshot().iter().map(|w| w.id).collect();
+        assert_eq!(ids, [2, 3, 4, 5]);
+    }
+}
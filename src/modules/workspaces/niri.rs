@@ -0,0 +1,426 @@
+//! niri workspaces over its JSON IPC (`NIRI_SOCKET`).
+//!
+//! The event stream starts with the full workspace and window lists and then
+//! sends changes, so the state is tracked from events without querying.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use super::ipc::{self, SnapshotSender};
+use super::{WorkspaceBackend, WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+
+#[derive(Deserialize)]
+struct NiriWorkspace {
+    id: u64,
+    /// 1-based position on its output
+    idx: u32,
+    name: Option<String>,
+    output: Option<String>,
+    /// Added in niri 25.02
+    #[serde(default)]
+    is_urgent: bool,
+    /// Shown on its output
+    is_active: bool,
+    is_focused: bool,
+}
+
+#[derive(Deserialize)]
+struct NiriWindow {
+    id: u64,
+    app_id: Option<String>,
+    workspace_id: Option<u64>,
+}
+
+#[derive(Default)]
+struct NiriState {
+    workspaces: Vec<NiriWorkspace>,
+    windows: Vec<NiriWindow>,
+}
+
+impl NiriState {
+    /// Apply one event line; returns whether anything shown may have changed
+    fn apply(&mut self, line: &str) -> Result<bool, serde_json::Error> {
+        let event: HashMap<String, serde_json::Value> = serde_json::from_str(line)?;
+        let Some((kind, body)) = event.into_iter().next() else {
+            return Ok(false);
+        };
+
+        match kind.as_str() {
+            "WorkspacesChanged" => {
+                self.workspaces = serde_json::from_value(body["workspaces"].clone())?;
+            }
+            "WorkspaceActivated" => {
+                let id = body["id"].as_u64();
+                let output = self
+                    .workspaces
+                    .iter()
+                    .find(|w| Some(w.id) == id)
+                    .and_then(|w| w.output.clone());
+                let focused = body["focused"].as_bool().unwrap_or(false);
+                for ws in &mut self.workspaces {
+                    if ws.output == output {
+                        ws.is_active = Some(ws.id) == id;
+                    }
+                    if focused {
+                        ws.is_focused = Some(ws.id) == id;
+                    }
+                }
+            }
+            "WorkspaceUrgencyChanged" => {
+                let id = body["id"].as_u64();
+                if let Some(ws) = self.workspaces.iter_mut().find(|w| Some(w.id) == id) {
+                    ws.is_urgent = body["urgent"].as_bool().unwrap_or(false);
+                }
+            }
+            "WindowsChanged" => {
+                self.windows = serde_json::from_value(body["windows"].clone())?;
+            }
+            "WindowOpenedOrChanged" => {
+                let window: NiriWindow = serde_json::from_value(body["window"].clone())?;
+                match self.windows.iter_mut().find(|w| w.id == window.id) {
+                    Some(existing) => *existing = window,
+                    None => self.windows.push(window),
+                }
+            }
+            "WindowClosed" => {
+                let id = body["id"].as_u64();
+                self.windows.retain(|w| Some(w.id) != id);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn snapshot(&self) -> Vec<(WorkspaceInfo, String)> {
+        self.workspaces
+            .iter()
+            .map(|ws| {
+                let info = WorkspaceInfo {
+                    id: ws.id,
+                    name: ws.name.clone().unwrap_or_default(),
+                    index: ws.idx,
+                    group: 0,
+                    active: ws.is_active,
+                    focused: ws.is_focused,
+                    urgent: ws.is_urgent,
+                    hidden: false,
+                    outputs: Vec::new(),
+                    can_remove: false,
+                    can_assign: false,
+                    windows: self
+                        .windows
+                        .iter()
+                        .filter(|w| w.workspace_id == Some(ws.id))
+                        .filter_map(|w| w.app_id.clone())
+                        .collect(),
+                };
+                (info, ws.output.clone().unwrap_or_default())
+            })
+            .collect()
+    }
+}
+
+pub(super) fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("NIRI_SOCKET")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Send one request on `stream` and read its `{"Ok": ...}` / `{"Err": ...}`
+/// reply line
+fn call(
+    stream: &mut BufReader<UnixStream>,
+    request: &serde_json::Value,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut msg = request.to_string();
+    msg.push('\n');
+    stream.get_mut().write_all(msg.as_bytes())?;
+    stream.get_mut().flush()?;
+
+    let mut line = String::new();
+    if stream.read_line(&mut line)? == 0 {
+        return Err("niri closed the socket".into());
+    }
+    let mut reply: serde_json::Value = serde_json::from_str(&line)?;
+    if let Some(error) = reply.get("Err") {
+        return Err(error.to_string().into());
+    }
+    Ok(reply["Ok"].take())
+}
+
+fn handle_request(
+    socket_path: &Path,
+    request: WorkspaceRequest,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match request {
+        WorkspaceRequest::Activate(id) => {
+            let mut stream = BufReader::new(UnixStream::connect(socket_path)?);
+            let action = serde_json::json!({
+                "Action": { "FocusWorkspace": { "reference": { "Id": id } } }
+            });
+            call(&mut stream, &action)?;
+        }
+        // Never offered: niri creates and removes workspaces on its own and
+        // niri snapshots report no assign capability
+        WorkspaceRequest::Create { .. }
+        | WorkspaceRequest::Remove(_)
+        | WorkspaceRequest::Assign { .. } => {}
+    }
+    Ok(())
+}
+
+pub(super) struct NiriBackend {
+    pub(super) socket_path: PathBuf,
+}
+
+impl WorkspaceBackend for NiriBackend {
+    fn name(&self) -> &'static str {
+        "niri IPC"
+    }
+
+    /// Watch niri's workspaces until the receiver is dropped, reconnecting if the
+    /// socket goes away
+    fn run(
+        self: Box<Self>,
+        event_tx: mpsc::Sender<WorkspaceEvent>,
+        request_rx: mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        ipc::run_reconnecting(self.name(), event_tx, request_rx, |sender, request_rx| {
+            watch(&self.socket_path, sender, request_rx)
+        })
+    }
+}
+
+fn watch(
+    socket_path: &Path,
+    sender: &mut SnapshotSender,
+    request_rx: &mut mpsc::Receiver<WorkspaceRequest>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut events = BufReader::new(UnixStream::connect(socket_path)?);
+    call(&mut events, &serde_json::json!("EventStream"))?;
+    debug!("Workspaces: using niri IPC at {}", socket_path.display());
+
+    let mut state = NiriState::default();
+    let mut changed = false;
+    let mut line = String::new();
+    loop {
+        // Nothing to show until the first workspace list arrives
+        if changed && !state.workspaces.is_empty() {
+            if !sender.send(state.snapshot()) {
+                return Ok(());
+            }
+            changed = false;
+        }
+
+        loop {
+            match request_rx.try_recv() {
+                Ok(request) => {
+                    if let Err(e) = handle_request(socket_path, request) {
+                        warn!("niri workspace request failed: {e}");
+                    }
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        while ipc::wait_readable(&events, ipc::POLL_MS) {
+            line.clear();
+            if events.read_line(&mut line)? == 0 {
+                return Err("event stream closed".into());
+            }
+            match state.apply(&line) {
+                Ok(c) => changed |= c,
+                Err(e) => debug!("niri: skipping event: {e}"),
+            }
+            if events.buffer().is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use super::super::ipc::script::{self, Watcher};
+    use super::*;
+
+    // Start of a niri 25.02 event stream, trimmed to the fields read here
+    const STREAM: &[&str] = &[
+        r#"{"WorkspacesChanged":{"workspaces":[
+            {"id":1,"idx":1,"name":null,"output":"DP-1","is_urgent":false,
+             "is_active":true,"is_focused":true,"active_window_id":10},
+            {"id":2,"idx":2,"name":"web","output":"DP-1","is_urgent":false,
+             "is_active":false,"is_focused":false,"active_window_id":null},
+            {"id":3,"idx":1,"name":null,"output":"HDMI-A-1","is_urgent":false,
+             "is_active":true,"is_focused":false,"active_window_id":null}]}}"#,
+        r#"{"WindowsChanged":{"windows":[
+            {"id":10,"title":"~","app_id":"foot","pid":100,"workspace_id":1,
+             "is_focused":true,"is_floating":false,"is_urgent":false},
+            {"id":11,"title":"Mozilla Firefox","app_id":"firefox","pid":101,
+             "workspace_id":2,"is_focused":false,"is_floating":false,"is_urgent":false}]}}"#,
+        r#"{"KeyboardLayoutsChanged":{"keyboard_layouts":{"names":["English (US)"],"current_idx":0}}}"#,
+    ];
+
+    fn started() -> NiriState {
+        let mut state = NiriState::default();
+        for line in STREAM {
+            state.apply(line).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn builds_list_from_initial_events() {
+        let mut state = NiriState::default();
+        let changed: Vec<bool> = STREAM.iter().map(|l| state.apply(l).unwrap()).collect();
+        assert_eq!(changed, [true, true, false]);
+
+        let list = state.snapshot();
+        let summary: Vec<_> = list
+            .iter()
+            .map(|(w, output)| (w.id, w.index, w.name.as_str(), output.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, 1, "", "DP-1"),
+                (2, 2, "web", "DP-1"),
+                (3, 1, "", "HDMI-A-1")
+            ]
+        );
+        assert_eq!(list[0].0.windows, ["foot"]);
+        assert_eq!(list[1].0.windows, ["firefox"]);
+        assert!(list[0].0.focused && list[0].0.active && list[2].0.active);
+    }
+
+    #[test]
+    fn activation_moves_within_the_output() {
+        let mut state = started();
+        assert!(state
+            .apply(r#"{"WorkspaceActivated":{"id":2,"focused":true}}"#)
+            .unwrap());
+        let flags: Vec<_> = state
+            .snapshot()
+            .iter()
+            .map(|(w, _)| (w.active, w.focused))
+            .collect();
+        assert_eq!(flags, [(false, false), (true, true), (true, false)]);
+
+        // Activating on another output without focus leaves focus alone
+        state
+            .apply(r#"{"WorkspaceActivated":{"id":3,"focused":false}}"#)
+            .unwrap();
+        assert!(state.snapshot()[1].0.focused);
+    }
+
+    #[test]
+    fn tracks_window_and_urgency_changes() {
+        let mut state = started();
+        state
+            .apply(r#"{"WindowOpenedOrChanged":{"window":{"id":12,"title":"mpv","app_id":"mpv","workspace_id":3,"is_focused":false}}}"#)
+            .unwrap();
+        state
+            .apply(r#"{"WindowOpenedOrChanged":{"window":{"id":10,"title":"~","app_id":"foot","workspace_id":2,"is_focused":true}}}"#)
+            .unwrap();
+        state.apply(r#"{"WindowClosed":{"id":11}}"#).unwrap();
+        state
+            .apply(r#"{"WorkspaceUrgencyChanged":{"id":3,"urgent":true}}"#)
+            .unwrap();
+
+        let list = state.snapshot();
+        assert!(list[0].0.windows.is_empty());
+        assert_eq!(list[1].0.windows, ["foot"]);
+        assert_eq!(list[2].0.windows, ["mpv"]);
+        assert!(list[2].0.urgent);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let mut state = started();
+        assert!(state.apply("not json").is_err());
+        assert!(state
+            .apply(r#"{"WorkspacesChanged":{"workspaces":[{"id":"x"}]}}"#)
+            .is_err());
+        assert!(!state.apply("{}").unwrap());
+        assert_eq!(state.snapshot().len(), 3);
+    }
+
+    #[test]
+    fn watches_and_activates_over_the_socket() {
+        let dir = script::socket_dir("niri");
+        let path = dir.join("niri.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let (shown_tx, shown_rx) = std::sync::mpsc::channel();
+
+        let server = std::thread::spawn(move || {
+            let accept = || {
+                let (stream, _) = listener.accept().unwrap();
+                stream.set_read_timeout(Some(script::TIMEOUT)).unwrap();
+                BufReader::new(stream)
+            };
+            let mut seen = Vec::new();
+            let mut read_request = |stream: &mut BufReader<UnixStream>| {
+                let mut line = String::new();
+                stream.read_line(&mut line).unwrap();
+                seen.push(line);
+            };
+
+            let mut events = accept();
+            read_request(&mut events);
+            // The reply and the initial events in one write, as niri sends
+            // them before the watcher gets to read
+            let mut start = String::from("{\"Ok\":\"Handled\"}\n");
+            for line in STREAM {
+                let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                start.push_str(&format!("{event}\n"));
+            }
+            events.get_mut().write_all(start.as_bytes()).unwrap();
+            // Kept apart from the initial events so it makes a snapshot of
+            // its own
+            shown_rx.recv_timeout(script::TIMEOUT).unwrap();
+            events
+                .get_mut()
+                .write_all(b"{\"WorkspaceActivated\":{\"id\":2,\"focused\":true}}\n")
+                .unwrap();
+
+            let mut action = accept();
+            read_request(&mut action);
+            action
+                .get_mut()
+                .write_all(b"{\"Ok\":\"Handled\"}\n")
+                .unwrap();
+            (seen, events)
+        });
+
+        let mut watcher = Watcher::start(watch, path);
+        let list = watcher.snapshot();
+        let summary: Vec<_> = list.iter().map(|w| (w.id, w.group, w.focused)).collect();
+        assert_eq!(summary, [(1, 1, true), (2, 1, false), (3, 2, false)]);
+        assert_eq!(list[1].windows, ["firefox"]);
+        shown_tx.send(()).unwrap();
+        let focused: Vec<_> = watcher.snapshot().iter().map(|w| w.focused).collect();
+        assert_eq!(focused, [false, true, false]);
+
+        watcher.send(WorkspaceRequest::Activate(3));
+        let (seen, _events) = server.join().unwrap();
+        assert_eq!(
+            seen,
+            [
+                "\"EventStream\"\n",
+                "{\"Action\":{\"FocusWorkspace\":{\"reference\":{\"Id\":3}}}}\n",
+            ]
+        );
+
+        assert_eq!(watcher.stop(), Ok(()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,446 @@
+//! sway workspaces over i3-IPC (`SWAYSOCK`)
+
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use super::ipc::{self, SnapshotSender};
+use super::{WorkspaceBackend, WorkspaceEvent, WorkspaceInfo, WorkspaceRequest};
+
+const MAGIC: &[u8; 6] = b"i3-ipc";
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_TREE: u32 = 4;
+
+/// Event types have the high bit set
+const EVENT_WINDOW: u32 = 0x8000_0003;
+
+#[derive(Deserialize)]
+struct SwayWorkspace {
+    /// Container id, stable while the workspace exists
+    id: u64,
+    /// Leading number of the name, -1 for purely named workspaces
+    num: i32,
+    name: String,
+    /// Shown on its output
+    visible: bool,
+    focused: bool,
+    urgent: bool,
+    output: String,
+}
+
+#[derive(Deserialize)]
+struct ChangeEvent {
+    change: String,
+}
+
+pub(super) fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("SWAYSOCK")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+fn write_message(stream: &mut impl Write, kind: u32, payload: &[u8]) -> io::Result<()> {
+    let mut msg = Vec::with_capacity(MAGIC.len() + 8 + payload.len());
+    msg.extend_from_slice(MAGIC);
+    msg.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    msg.extend_from_slice(&kind.to_ne_bytes());
+    msg.extend_from_slice(payload);
+    stream.write_all(&msg)?;
+    stream.flush()
+}
+
+fn read_message(stream: &mut impl Read) -> io::Result<(u32, Vec<u8>)> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "bad i3-ipc magic",
+        ));
+    }
+    let len = u32::from_ne_bytes(header[6..10].try_into().unwrap_or([0; 4]));
+    let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap_or([0; 4]));
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload)?;
+    Ok((kind, payload))
+}
+
+fn call(stream: &mut UnixStream, kind: u32, payload: &[u8]) -> io::Result<Vec<u8>> {
+    write_message(stream, kind, payload)?;
+    let (_, reply) = read_message(stream)?;
+    Ok(reply)
+}
+
+/// App ids of the windows in `node`, keyed by workspace container id
+fn collect_windows(
+    node: &serde_json::Value,
+    workspace: Option<u64>,
+    out: &mut HashMap<u64, Vec<String>>,
+) {
+    let workspace = if node["type"] == "workspace" {
+        node["id"].as_u64()
+    } else {
+        workspace
+    };
+
+    // Leaf containers carry a pid; XWayland windows have a class instead of
+    // an app_id
+    if let (Some(ws), Some(_)) = (workspace, node.get("pid")) {
+        let app_id = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str());
+        if let Some(app_id) = app_id {
+            out.entry(ws).or_default().push(app_id.to_string());
+        }
+    }
+
+    for key in ["nodes", "floating_nodes"] {
+        if let Some(children) = node[key].as_array() {
+            for child in children {
+                collect_windows(child, workspace, out);
+            }
+        }
+    }
+}
+
+fn query_workspaces(
+    stream: &mut UnixStream,
+    names: &mut HashMap<u64, String>,
+) -> Result<Vec<(WorkspaceInfo, String)>, Box<dyn std::error::Error>> {
+    let workspaces = call(stream, GET_WORKSPACES, b"")?;
+    let tree = call(stream, GET_TREE, b"")?;
+    Ok(parse_workspaces(&workspaces, &tree, names)?)
+}
+
+/// Build the list from GET_WORKSPACES and GET_TREE replies
+fn parse_workspaces(
+    workspaces: &[u8],
+    tree: &[u8],
+    names: &mut HashMap<u64, String>,
+) -> Result<Vec<(WorkspaceInfo, String)>, serde_json::Error> {
+    let workspaces: Vec<SwayWorkspace> = serde_json::from_slice(workspaces)?;
+    let tree: serde_json::Value = serde_json::from_slice(tree)?;
+    let mut windows = HashMap::new();
+    collect_windows(&tree, None, &mut windows);
+
+    names.clear();
+    // sway lists named workspaces after the numbered ones; they count on
+    // from the highest number so indices stay unique on the output
+    let mut last_index: HashMap<String, u32> = HashMap::new();
+    let mut out = Vec::with_capacity(workspaces.len());
+    for ws in workspaces {
+        let last = last_index.entry(ws.output.clone()).or_insert(0);
+        *last = u32::try_from(ws.num)
+            .ok()
+            .filter(|n| *n > 0)
+            .unwrap_or(*last + 1);
+        names.insert(ws.id, ws.name.clone());
+        let info = WorkspaceInfo {
+            id: ws.id,
+            index: *last,
+            name: ws.name,
+            group: 0,
+            active: ws.visible,
+            focused: ws.focused,
+            urgent: ws.urgent,
+            hidden: false,
+            outputs: Vec::new(),
+            can_remove: false,
+            can_assign: false,
+            windows: windows.remove(&ws.id).unwrap_or_default(),
+        };
+        out.push((info, ws.output));
+    }
+    Ok(out)
+}
+
+/// Whether an event can change the list. Window focus and title changes
+/// don't move anything between workspaces.
+fn needs_refresh(kind: u32, payload: &[u8]) -> bool {
+    kind != EVENT_WINDOW
+        || serde_json::from_slice::<ChangeEvent>(payload)
+            .is_ok_and(|e| matches!(e.change.as_str(), "new" | "close" | "move" | "urgent"))
+}
+
+fn handle_request(
+    stream: &mut UnixStream,
+    names: &HashMap<u64, String>,
+    request: WorkspaceRequest,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match request {
+        WorkspaceRequest::Activate(id) => {
+            let Some(name) = names.get(&id) else {
+                return Ok(());
+            };
+            let cmd = format!(
+                "workspace --no-auto-back-and-forth \"{}\"",
+                name.replace('\\', "\\\\").replace('"', "\\\"")
+            );
+            let reply: Vec<serde_json::Value> =
+                serde_json::from_slice(&call(stream, RUN_COMMAND, cmd.as_bytes())?)?;
+            if let Some(error) = reply.iter().find_map(|r| r["error"].as_str()) {
+                return Err(error.into());
+            }
+        }
+        // Never offered: sway snapshots report no create/remove/assign
+        // capabilities
+        WorkspaceRequest::Create { .. }
+        | WorkspaceRequest::Remove(_)
+        | WorkspaceRequest::Assign { .. } => {}
+    }
+    Ok(())
+}
+
+pub(super) struct SwayBackend {
+    pub(super) socket_path: PathBuf,
+}
+
+impl WorkspaceBackend for SwayBackend {
+    fn name(&self) -> &'static str {
+        "sway IPC"
+    }
+
+    /// Watch sway's workspaces until the receiver is dropped, reconnecting if
+    /// the socket goes away
+    fn run(
+        self: Box<Self>,
+        event_tx: mpsc::Sender<WorkspaceEvent>,
+        request_rx: mpsc::Receiver<WorkspaceRequest>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        ipc::run_reconnecting(self.name(), event_tx, request_rx, |sender, request_rx| {
+            watch(&self.socket_path, sender, request_rx)
+        })
+    }
+}
+
+fn watch(
+    socket_path: &Path,
+    sender: &mut SnapshotSender,
+    request_rx: &mut mpsc::Receiver<WorkspaceRequest>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Replies and events interleave on a subscribed socket, so queries and
+    // commands go over a second connection
+    let mut events = UnixStream::connect(socket_path)?;
+    let mut commands = UnixStream::connect(socket_path)?;
+
+    let reply: serde_json::Value =
+        serde_json::from_slice(&call(&mut events, SUBSCRIBE, br#"["workspace","window"]"#)?)?;
+    if reply["success"] != true {
+        return Err(format!("subscribe failed: {reply}").into());
+    }
+    let mut events = BufReader::new(events);
+    debug!("Workspaces: using sway IPC at {}", socket_path.display());
+
+    let mut names = HashMap::new();
+    let mut refresh = true;
+    loop {
+        if refresh {
+            let list = query_workspaces(&mut commands, &mut names)?;
+            if !sender.send(list) {
+                return Ok(());
+            }
+            refresh = false;
+        }
+
+        loop {
+            match request_rx.try_recv() {
+                Ok(request) => {
+                    if let Err(e) = handle_request(&mut commands, &names, request) {
+                        warn!("sway workspace request failed: {e}");
+                    }
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        while ipc::wait_readable(&events, ipc::POLL_MS) {
+            let (kind, payload) = read_message(&mut events)?;
+            refresh |= needs_refresh(kind, &payload);
+            if events.buffer().is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use super::super::ipc::script::{self, Watcher};
+    use super::*;
+
+    // Trimmed from a sway 1.9 session: two outputs, a named workspace and
+    // an XWayland window
+    const WORKSPACES: &str = r#"[
+        {"id": 4, "type": "workspace", "num": 1, "name": "1", "visible": true,
+         "focused": false, "urgent": false, "output": "DP-1"},
+        {"id": 9, "type": "workspace", "num": 3, "name": "3:web", "visible": false,
+         "focused": false, "urgent": true, "output": "DP-1"},
+        {"id": 12, "type": "workspace", "num": -1, "name": "mail", "visible": false,
+         "focused": false, "urgent": false, "output": "DP-1"},
+        {"id": 15, "type": "workspace", "num": 2, "name": "2", "visible": true,
+         "focused": true, "urgent": false, "output": "HDMI-A-1"}
+    ]"#;
+
+    const TREE: &str = r#"{"id": 1, "type": "root", "nodes": [
+        {"id": 2, "type": "output", "name": "DP-1", "nodes": [
+            {"id": 4, "type": "workspace", "name": "1", "nodes": [
+                {"id": 5, "type": "con", "pid": 100, "app_id": "foot", "nodes": []},
+                {"id": 6, "type": "con", "nodes": [
+                    {"id": 7, "type": "con", "pid": 101, "app_id": null,
+                     "window_properties": {"class": "Steam"}, "nodes": []}
+                ]}
+            ], "floating_nodes": []},
+            {"id": 9, "type": "workspace", "name": "3:web", "nodes": [],
+             "floating_nodes": [
+                {"id": 10, "type": "floating_con", "pid": 102, "app_id": "firefox",
+                 "nodes": []}
+            ]},
+            {"id": 12, "type": "workspace", "name": "mail", "nodes": [],
+             "floating_nodes": []}
+        ]},
+        {"id": 13, "type": "output", "name": "HDMI-A-1", "nodes": [
+            {"id": 15, "type": "workspace", "name": "2", "nodes": [],
+             "floating_nodes": []}
+        ]}
+    ]}"#;
+
+    #[test]
+    fn parses_workspace_and_tree_replies() {
+        let mut names = HashMap::new();
+        let list = parse_workspaces(WORKSPACES.as_bytes(), TREE.as_bytes(), &mut names).unwrap();
+
+        let summary: Vec<_> = list
+            .iter()
+            .map(|(w, output)| (w.id, w.index, w.name.as_str(), output.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (4, 1, "1", "DP-1"),
+                (9, 3, "3:web", "DP-1"),
+                (12, 4, "mail", "DP-1"),
+                (15, 2, "2", "HDMI-A-1"),
+            ]
+        );
+
+        let (first, _) = &list[0];
+        assert!(first.active && !first.focused);
+        assert_eq!(first.windows, ["foot", "Steam"]);
+        let (web, _) = &list[1];
+        assert!(web.urgent && !web.active);
+        assert_eq!(web.windows, ["firefox"]);
+        assert!(list[2].0.windows.is_empty());
+        assert!(list[3].0.focused);
+        assert_eq!(names.get(&12).map(String::as_str), Some("mail"));
+    }
+
+    #[test]
+    fn reads_framed_messages() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, EVENT_WINDOW, br#"{"change":"focus"}"#).unwrap();
+        write_message(&mut buf, GET_WORKSPACES, b"[]").unwrap();
+
+        let mut reader = buf.as_slice();
+        let (kind, payload) = read_message(&mut reader).unwrap();
+        assert_eq!(kind, EVENT_WINDOW);
+        assert!(!needs_refresh(kind, &payload));
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            (GET_WORKSPACES, b"[]".to_vec())
+        );
+
+        let mut bad: &[u8] = b"i3-pc\0\0\0\0\0\0\0\0\0";
+        assert!(read_message(&mut bad).is_err());
+    }
+
+    #[test]
+    fn refreshes_on_events_that_move_windows() {
+        let workspace_event = 0x8000_0000;
+        assert!(needs_refresh(workspace_event, br#"{"change":"focus"}"#));
+        for change in ["new", "close", "move", "urgent"] {
+            let payload = format!(r#"{{"change":"{change}","container":{{}}}}"#);
+            assert!(needs_refresh(EVENT_WINDOW, payload.as_bytes()), "{change}");
+        }
+        assert!(!needs_refresh(EVENT_WINDOW, br#"{"change":"title"}"#));
+        assert!(!needs_refresh(EVENT_WINDOW, b"not json"));
+    }
+
+    #[test]
+    fn watches_and_activates_over_the_socket() {
+        let dir = script::socket_dir("sway");
+        let path = dir.join("sway-ipc.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let renamed = WORKSPACES.replace(r#""mail""#, r#""say \"hi\"""#);
+
+        let server = std::thread::spawn(move || {
+            let accept = || {
+                let (stream, _) = listener.accept().unwrap();
+                stream.set_read_timeout(Some(script::TIMEOUT)).unwrap();
+                stream
+            };
+            // The watcher connects its event socket first
+            let mut events = accept();
+            let mut commands = accept();
+            let mut seen = Vec::new();
+            let mut reply = |stream: &mut UnixStream, payload: &str| {
+                let (kind, body) = read_message(stream).unwrap();
+                seen.push((kind, String::from_utf8(body).unwrap()));
+                write_message(stream, kind, payload.as_bytes()).unwrap();
+            };
+
+            reply(&mut events, r#"{"success":true}"#);
+            reply(&mut commands, WORKSPACES);
+            reply(&mut commands, TREE);
+            write_message(&mut events, 0x8000_0000, br#"{"change":"rename"}"#).unwrap();
+            reply(&mut commands, &renamed);
+            reply(&mut commands, TREE);
+            reply(&mut commands, r#"[{"success":true}]"#);
+            (seen, events, commands)
+        });
+
+        let mut watcher = Watcher::start(watch, path);
+        let summary = |list: &[WorkspaceInfo]| -> Vec<(u64, String, u32)> {
+            list.iter()
+                .map(|w| (w.id, w.name.clone(), w.group))
+                .collect()
+        };
+        assert_eq!(
+            summary(&watcher.snapshot()),
+            [
+                (4, "1".into(), 1),
+                (9, "3:web".into(), 1),
+                (12, "mail".into(), 1),
+                (15, "2".into(), 2),
+            ]
+        );
+        let list = watcher.snapshot();
+        assert_eq!(list[2].name, r#"say "hi""#);
+        assert_eq!(list[0].windows, ["foot", "Steam"]);
+
+        watcher.send(WorkspaceRequest::Activate(12));
+        let (seen, _events, _commands) = server.join().unwrap();
+        let query = [(GET_WORKSPACES, String::new()), (GET_TREE, String::new())];
+        let mut expected = vec![(SUBSCRIBE, r#"["workspace","window"]"#.to_string())];
+        expected.extend(query.clone());
+        expected.extend(query);
+        expected.push((
+            RUN_COMMAND,
+            r#"workspace --no-auto-back-and-forth "say \"hi\"""#.to_string(),
+        ));
+        assert_eq!(seen, expected);
+
+        assert_eq!(watcher.stop(), Ok(()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use tracing::warn;
+use zbus::zvariant::Value;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Urgency {
+    Normal = 1,
+    Critical = 2,
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// Show a desktop notification in the background. Failures (no session bus,
+/// no notification daemon) are logged and otherwise ignored.
+pub fn send(summary: String, body: String, icon: &'static str, urgency: Urgency) {
+    crate::spawn(async move {
+        if let Err(e) = notify(&summary, &body, icon, urgency).await {
+            warn!("Failed to send notification \"{summary}\": {e}");
+        }
+    });
+}
+
+async fn notify(summary: &str, body: &str, icon: &str, urgency: Urgency) -> zbus::Result<()> {
+    let conn = zbus::Connection::session().await?;
+    let proxy = NotificationsProxy::new(&conn).await?;
+
+    let mut hints = HashMap::new();
+    hints.insert("urgency", Value::U8(urgency as u8));
+
+    // -1 lets the server pick the timeout; critical ones usually stay up
+    proxy
+        .notify("ferritebar", 0, icon, summary, body, &[], hints, -1)
+        .await?;
+    Ok(())
+}
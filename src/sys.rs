@@ -0,0 +1,75 @@
+//! The few raw Linux calls the watchers need, declared with bare
+//! `extern "C"` to avoid adding `libc`/`nix` as dependencies.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::raw::{c_int, c_short, c_void};
+
+const AF_NETLINK: c_int = 16;
+const SOCK_RAW: c_int = 3;
+const SOCK_CLOEXEC: c_int = 0o2000000;
+
+/// Netlink receive buffer overrun: the kernel dropped messages
+pub const ENOBUFS: i32 = 105;
+
+#[repr(C)]
+struct SockaddrNl {
+    family: u16,
+    pad: u16,
+    pid: u32,
+    groups: u32,
+}
+
+#[repr(C)]
+struct PollFd {
+    fd: c_int,
+    events: c_short,
+    revents: c_short,
+}
+
+extern "C" {
+    fn socket(domain: c_int, ty: c_int, protocol: c_int) -> c_int;
+    fn bind(fd: c_int, addr: *const c_void, len: u32) -> c_int;
+    // nfds_t is `unsigned long` on Linux
+    fn poll(fds: *mut PollFd, nfds: u64, timeout: c_int) -> c_int;
+}
+
+/// Open a netlink socket for `protocol`, bound to the multicast `groups`
+pub fn netlink_socket(protocol: c_int, groups: u32) -> io::Result<OwnedFd> {
+    let fd = unsafe { socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, protocol) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd was just returned by socket() and has no other owner.
+    let owned = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let addr = SockaddrNl {
+        family: AF_NETLINK as u16,
+        pad: 0,
+        pid: 0,
+        groups,
+    };
+    let rc = unsafe {
+        bind(
+            owned.as_raw_fd(),
+            &addr as *const SockaddrNl as *const c_void,
+            std::mem::size_of::<SockaddrNl>() as u32,
+        )
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(owned)
+}
+
+/// Poll a file descriptor for readability with a timeout. Returns `true` if
+/// the fd is readable before the timeout.
+pub fn poll_readable(raw_fd: i32, timeout_ms: i32) -> bool {
+    const POLLIN: c_short = 0x001;
+    let mut pfd = PollFd {
+        fd: raw_fd,
+        events: POLLIN,
+        revents: 0,
+    };
+    unsafe { poll(&mut pfd, 1, timeout_ms) > 0 }
+}
@@ -616,6 +616,7 @@ window {{
     background: transparent;
     color: {fg};
     min-height: {bar_h}px;
+    transition: background-color 150ms ease-out, color 150ms ease-out, opacity 150ms ease-out;
 }}
 
 .taskbar-button:hover {{
@@ -645,6 +646,11 @@ window {{
     opacity: 0.6;
 }}
 
+.workspace-separator {{
+    color: alpha({fg}, 0.5);
+    margin: 0 2px;
+}}
+
 .power-popover {{
     background-color: alpha({menu_bg}, 0.96);
     border: 1px solid alpha({menu_fg}, 0.2);
@@ -756,6 +762,16 @@ window {{
     font-weight: bold;
 }}
 
+.wifi-picker .wifi-signal,
+.wifi-picker .wifi-security {{
+    color: alpha({menu_fg}, 0.6);
+    font-size: 0.9em;
+}}
+
+.wifi-picker .wifi-password {{
+    margin: 4px 8px;
+}}
+
 .connected label.module-label {{
     color: {success};
 }}
@@ -776,10 +792,31 @@ window {{
     color: {warning};
 }}
 
+.warning label.module-label {{
+    color: {warning};
+}}
+
+.battery .peripherals {{
+    margin-left: 6px;
+    font-size: 0.85em;
+}}
+
+.battery .peripheral.low {{
+    color: {warning};
+}}
+
 .critical label.module-label {{
     color: {error};
 }}
 
+.power-profile.performance label.module-label {{
+    color: {warning};
+}}
+
+.power-profile.power-saver label.module-label {{
+    color: {success};
+}}
+
 tooltip, tooltip.background {{
     background-color: alpha({menu_bg}, 0.96);
     color: {menu_fg};
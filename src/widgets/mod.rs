@@ -1 +1,2 @@
 pub mod mini_bar;
+pub mod popup_menu;
@@ -0,0 +1,155 @@
+use gtk::prelude::*;
+use gtk_layer_shell::LayerShell;
+
+use crate::config::types::Position;
+
+/// A list of actions in a layer-shell popup next to the bar, styled like the
+/// tray's context menus. One instance is reused for every menu a module opens;
+/// modules needing more than plain entries (the Wi-Fi picker) add their own
+/// widgets with [`PopupMenu::append`].
+pub struct PopupMenu {
+    window: gtk::ApplicationWindow,
+    content: gtk::Box,
+}
+
+impl PopupMenu {
+    pub fn new(
+        app: &gtk::Application,
+        namespace: &str,
+        bar_position: Position,
+        bar_height: u32,
+        bar_edge_margin: i32,
+    ) -> Self {
+        let window = gtk::ApplicationWindow::builder()
+            .application(app)
+            .default_width(200)
+            .default_height(0)
+            .build();
+
+        window.init_layer_shell();
+        window.set_layer(gtk_layer_shell::Layer::Overlay);
+        window.set_namespace(Some(namespace));
+        window.set_keyboard_mode(gtk_layer_shell::KeyboardMode::OnDemand);
+
+        // Anchor to bar edge + left (not right, so it doesn't stretch)
+        match bar_position {
+            Position::Top => {
+                window.set_anchor(gtk_layer_shell::Edge::Top, true);
+                window.set_anchor(gtk_layer_shell::Edge::Bottom, false);
+                window.set_margin(
+                    gtk_layer_shell::Edge::Top,
+                    bar_height as i32 + bar_edge_margin,
+                );
+            }
+            Position::Bottom => {
+                window.set_anchor(gtk_layer_shell::Edge::Top, false);
+                window.set_anchor(gtk_layer_shell::Edge::Bottom, true);
+                window.set_margin(
+                    gtk_layer_shell::Edge::Bottom,
+                    bar_height as i32 + bar_edge_margin,
+                );
+            }
+        }
+        window.set_anchor(gtk_layer_shell::Edge::Left, true);
+        window.set_anchor(gtk_layer_shell::Edge::Right, false);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        content.add_css_class("tray-menu");
+        window.set_child(Some(&content));
+        window.set_visible(false);
+
+        // Dismiss on Escape
+        let key_ctrl = gtk::EventControllerKey::new();
+        let window_esc = window.clone();
+        key_ctrl.connect_key_pressed(move |_, key, _, _| {
+            if key == gtk::gdk::Key::Escape {
+                window_esc.set_visible(false);
+                return glib::Propagation::Stop;
+            }
+            glib::Propagation::Proceed
+        });
+        window.add_controller(key_ctrl);
+
+        // Dismiss on focus loss (click outside)
+        let window_focus = window.clone();
+        window.connect_is_active_notify(move |_| {
+            let w = window_focus.clone();
+            glib::idle_add_local_once(move || {
+                if !w.is_active() {
+                    w.set_visible(false);
+                }
+            });
+        });
+
+        Self { window, content }
+    }
+
+    /// Widen the popup beyond the default 200px
+    pub fn set_width(&self, width: i32) {
+        self.window.set_default_width(width);
+    }
+
+    /// Extra style class on the entry list, next to `tray-menu`
+    pub fn add_css_class(&self, class: &str) {
+        self.content.add_css_class(class);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.window.is_visible()
+    }
+
+    pub fn hide(&self) {
+        self.window.set_visible(false);
+    }
+
+    /// Remove all entries before filling the menu for a new target
+    pub fn clear(&self) {
+        while let Some(child) = self.content.first_child() {
+            self.content.remove(&child);
+        }
+    }
+
+    pub fn add_header(&self, text: &str) {
+        let header = gtk::Label::new(Some(text));
+        header.set_halign(gtk::Align::Start);
+        header.add_css_class("submenu-header");
+        self.content.append(&header);
+    }
+
+    /// Add a clickable entry; the menu closes before `on_activate` runs
+    pub fn add_item(&self, label: &str, on_activate: impl Fn() + 'static) {
+        let btn = gtk::Button::with_label(label);
+        btn.set_halign(gtk::Align::Fill);
+        if let Some(label) = btn.child().and_downcast::<gtk::Label>() {
+            label.set_halign(gtk::Align::Start);
+        }
+
+        let window = self.window.clone();
+        btn.connect_clicked(move |_| {
+            window.set_visible(false);
+            on_activate();
+        });
+        self.content.append(&btn);
+    }
+
+    /// Add a custom widget; unlike [`PopupMenu::add_item`] nothing closes
+    /// the menu for it
+    pub fn append(&self, widget: &impl IsA<gtk::Widget>) {
+        self.content.append(widget);
+    }
+
+    /// Show the menu lined up with `anchor`'s left edge
+    pub fn popup_at(&self, anchor: &impl IsA<gtk::Widget>) {
+        let anchor = anchor.as_ref();
+        if let Some(root) = anchor.root() {
+            if let Some(point) = anchor.compute_point(
+                &root.upcast::<gtk::Widget>(),
+                &gtk::graphene::Point::new(0.0, 0.0),
+            ) {
+                self.window
+                    .set_margin(gtk_layer_shell::Edge::Left, point.x() as i32);
+            }
+        }
+        self.window.present();
+    }
+}